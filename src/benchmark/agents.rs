@@ -0,0 +1,172 @@
+use burn::{prelude::Backend, tensor::Device};
+use rand::{SeedableRng, rngs::StdRng, seq::IndexedRandom};
+
+use crate::{
+    dqn::{policy, state::StateType},
+    game::{
+        board::{Board, Direction},
+        game_rng::RealGameRng,
+    },
+    search::expectimax::{Expectimax, LeafEvaluator},
+    training::game_model::GameModel,
+};
+
+// Picks a move for boards that aren't over
+pub(crate) trait Agent {
+    fn name(&self) -> String;
+    fn pick_direction(&mut self, board: &Board<RealGameRng>) -> Direction;
+}
+
+pub(crate) struct RandomAgent {
+    rng: StdRng,
+}
+
+impl RandomAgent {
+    pub(crate) fn new(seed: u64) -> Self {
+        RandomAgent {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Agent for RandomAgent {
+    fn name(&self) -> String {
+        "random".to_string()
+    }
+
+    fn pick_direction(&mut self, board: &Board<RealGameRng>) -> Direction {
+        *board.possible_actions().choose(&mut self.rng).unwrap()
+    }
+}
+
+// Takes the move gaining the most points right away, ties are broken randomly
+pub(crate) struct GreedyScoreAgent {
+    rng: StdRng,
+}
+
+impl GreedyScoreAgent {
+    pub(crate) fn new(seed: u64) -> Self {
+        GreedyScoreAgent {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Agent for GreedyScoreAgent {
+    fn name(&self) -> String {
+        "greedy score".to_string()
+    }
+
+    fn pick_direction(&mut self, board: &Board<RealGameRng>) -> Direction {
+        let scored_actions: Vec<(Direction, u32)> = board
+            .possible_actions()
+            .into_iter()
+            .map(|action| (action, board.advance(&action).score))
+            .collect();
+        let best_score = scored_actions
+            .iter()
+            .map(|(_, score)| *score)
+            .max()
+            .unwrap();
+        let best_actions: Vec<Direction> = scored_actions
+            .into_iter()
+            .filter(|(_, score)| *score == best_score)
+            .map(|(action, _)| action)
+            .collect();
+
+        *best_actions.choose(&mut self.rng).unwrap()
+    }
+}
+
+// Keeps the largest tiles in the bottom left corner by preferring down, then left, then right
+pub(crate) struct CornerAgent {}
+
+impl Agent for CornerAgent {
+    fn name(&self) -> String {
+        "corner".to_string()
+    }
+
+    fn pick_direction(&mut self, board: &Board<RealGameRng>) -> Direction {
+        let possible_actions = board.possible_actions();
+        [
+            Direction::Down,
+            Direction::Left,
+            Direction::Right,
+            Direction::Up,
+        ]
+        .into_iter()
+        .find(|direction| possible_actions.contains(direction))
+        .unwrap()
+    }
+}
+
+pub(crate) struct ModelAgent<B: Backend> {
+    name: String,
+    model: GameModel<B>,
+    device: Device<B>,
+}
+
+impl<B: Backend> ModelAgent<B> {
+    pub(crate) fn new(name: String, model: GameModel<B>, device: Device<B>) -> Self {
+        ModelAgent {
+            name,
+            model,
+            device,
+        }
+    }
+}
+
+impl<B: Backend> Agent for ModelAgent<B> {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn pick_direction(&mut self, board: &Board<RealGameRng>) -> Direction {
+        policy::best_action(board, &self.model, &self.device)
+    }
+}
+
+pub(crate) struct SearchAgent<E: LeafEvaluator> {
+    name: String,
+    search: Expectimax<E>,
+}
+
+impl<E: LeafEvaluator> SearchAgent<E> {
+    pub(crate) fn new(name: String, search: Expectimax<E>) -> Self {
+        SearchAgent { name, search }
+    }
+}
+
+impl<E: LeafEvaluator> Agent for SearchAgent<E> {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn pick_direction(&mut self, board: &Board<RealGameRng>) -> Direction {
+        self.search
+            .best_direction(board.pack())
+            .expect("Games that aren't over have a possible move")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::board::NUM_TILES;
+    use crate::game::board::Tile::{Empty, Value};
+
+    #[test]
+    fn greedy_agent_takes_the_largest_merge() {
+        let mut tiles = [Empty; NUM_TILES];
+        // 2 2 in the first row, 8 8 in the first column
+        tiles[0] = Value(8);
+        tiles[1] = Value(2);
+        tiles[2] = Value(2);
+        tiles[4] = Value(8);
+        let board = Board::new_with_tiles(tiles, RealGameRng::new_with_seed(0));
+
+        let direction = GreedyScoreAgent::new(0).pick_direction(&board);
+
+        assert!(matches!(direction, Direction::Up | Direction::Down));
+    }
+}
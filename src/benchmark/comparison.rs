@@ -0,0 +1,169 @@
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+use crate::{benchmark::agents::Agent, game::board::Board};
+
+// Plays the same seeded games with every agent, so that their results are directly comparable
+pub(crate) struct Benchmark {
+    num_games: usize,
+    seed: u64,
+}
+
+impl Benchmark {
+    pub(crate) fn new(num_games: usize, seed: u64) -> Self {
+        Benchmark { num_games, seed }
+    }
+
+    pub(crate) fn run(&self, agent: &mut dyn Agent) -> BenchmarkResult {
+        let mut result = BenchmarkResult {
+            agent_name: agent.name(),
+            scores: Vec::with_capacity(self.num_games),
+            max_tiles: Vec::with_capacity(self.num_games),
+            moves: Vec::with_capacity(self.num_games),
+            thinking_time: Duration::ZERO,
+        };
+
+        for game in 0..self.num_games {
+            let mut board = Board::new_with_seed(self.seed.wrapping_add(game as u64));
+            let mut moves = 0;
+
+            while !board.is_over() {
+                let start = Instant::now();
+                let direction = agent.pick_direction(&board);
+                result.thinking_time += start.elapsed();
+
+                board.move_in_direction(direction);
+                moves += 1;
+            }
+
+            result.scores.push(board.score);
+            result.max_tiles.push(board.max_tile_value());
+            result.moves.push(moves);
+        }
+
+        result
+    }
+}
+
+pub(crate) struct BenchmarkResult {
+    pub agent_name: String,
+    pub scores: Vec<u32>,
+    pub max_tiles: Vec<u32>,
+    pub moves: Vec<u32>,
+    pub thinking_time: Duration,
+}
+
+impl BenchmarkResult {
+    fn mean_score(&self) -> f64 {
+        mean(&self.scores)
+    }
+
+    fn score_percentile(&self, percentile: f64) -> u32 {
+        let mut scores = self.scores.clone();
+        scores.sort_unstable();
+        match scores.len() {
+            0 => 0,
+            len => scores[((len - 1) as f64 * percentile).round() as usize],
+        }
+    }
+
+    fn time_per_move(&self) -> Duration {
+        let moves: u32 = self.moves.iter().sum();
+        self.thinking_time
+            .checked_div(moves.max(1))
+            .unwrap_or_default()
+    }
+
+    fn max_tile_rate(&self, tile: u32) -> f64 {
+        match self.max_tiles.len() {
+            0 => 0.0,
+            games => {
+                let count = self.max_tiles.iter().filter(|max| **max == tile).count();
+                count as f64 / games as f64
+            }
+        }
+    }
+}
+
+fn mean(values: &[u32]) -> f64 {
+    if values.is_empty() {
+        0.0
+    } else {
+        values.iter().map(|value| *value as f64).sum::<f64>() / values.len() as f64
+    }
+}
+
+// Markdown tables with the score distribution, moves and time per move of every agent, and
+// the share of games ending with each max tile
+pub(crate) fn comparison_table(results: &[BenchmarkResult]) -> String {
+    let mut table = String::new();
+
+    writeln!(
+        table,
+        "| agent | games | mean score | min | 25% | median | 75% | max | moves per game | time per move |"
+    )
+    .unwrap();
+    writeln!(table, "|---|---|---|---|---|---|---|---|---|---|").unwrap();
+    for result in results {
+        writeln!(
+            table,
+            "| {} | {} | {:.1} | {} | {} | {} | {} | {} | {:.1} | {:.3?} |",
+            result.agent_name,
+            result.scores.len(),
+            result.mean_score(),
+            result.score_percentile(0.0),
+            result.score_percentile(0.25),
+            result.score_percentile(0.5),
+            result.score_percentile(0.75),
+            result.score_percentile(1.0),
+            mean(&result.moves),
+            result.time_per_move(),
+        )
+        .unwrap();
+    }
+
+    let tiles: BTreeSet<u32> = results
+        .iter()
+        .flat_map(|result| result.max_tiles.iter().copied())
+        .collect();
+    writeln!(table).unwrap();
+    write!(table, "| agent |").unwrap();
+    for tile in &tiles {
+        write!(table, " {} |", tile).unwrap();
+    }
+    writeln!(table).unwrap();
+    writeln!(table, "|---|{}", "---|".repeat(tiles.len())).unwrap();
+    for result in results {
+        write!(table, "| {} |", result.agent_name).unwrap();
+        for tile in &tiles {
+            write!(table, " {:.1}% |", result.max_tile_rate(*tile) * 100.0).unwrap();
+        }
+        writeln!(table).unwrap();
+    }
+
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::benchmark::agents::{CornerAgent, RandomAgent};
+
+    #[test]
+    fn agents_play_the_same_games() {
+        let benchmark = Benchmark::new(3, 7);
+
+        let corner = benchmark.run(&mut CornerAgent {});
+        let corner_again = benchmark.run(&mut CornerAgent {});
+        let random = benchmark.run(&mut RandomAgent::new(0));
+
+        assert_eq!(corner.scores, corner_again.scores);
+        assert_eq!(corner.moves.len(), 3);
+        assert_eq!(random.scores.len(), 3);
+
+        let table = comparison_table(&[corner, random]);
+        assert!(table.contains("| corner | 3 |"));
+        assert!(table.contains("| random | 3 |"));
+    }
+}
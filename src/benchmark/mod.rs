@@ -0,0 +1,2 @@
+pub(crate) mod agents;
+pub(crate) mod comparison;
@@ -0,0 +1,86 @@
+use std::error::Error;
+use std::fs;
+use std::marker::PhantomData;
+
+use burn::prelude::Backend;
+
+use crate::{
+    benchmark::{
+        agents::{Agent, CornerAgent, GreedyScoreAgent, ModelAgent, RandomAgent, SearchAgent},
+        comparison::{Benchmark, comparison_table},
+    },
+    cli::cli_options::BenchmarkOptions,
+    search::{expectimax::Expectimax, heuristic_evaluator::HeuristicEvaluator},
+    training::model_storage,
+};
+
+const DEFAULT_SEARCH_DEPTH: usize = 2;
+
+// Plays the same seeded games with the baseline agents, every given model and expectimax, and
+// prints a table comparing them
+pub(crate) struct AgentBenchmark<B: Backend> {
+    options: BenchmarkOptions,
+    _backend: PhantomData<B>,
+}
+
+impl<B: Backend> AgentBenchmark<B> {
+    pub(crate) fn new(options: BenchmarkOptions) -> AgentBenchmark<B> {
+        AgentBenchmark {
+            options,
+            _backend: PhantomData,
+        }
+    }
+
+    pub(crate) fn run(self) -> Result<(), Box<dyn Error>> {
+        let config = self.options.evaluation.config();
+        let benchmark = Benchmark::new(config.num_games, config.seed);
+
+        let mut results = Vec::new();
+        for mut agent in self.agents()? {
+            let result = benchmark.run(agent.as_mut());
+            println!(
+                "Finished {} games with {}",
+                result.scores.len(),
+                result.agent_name
+            );
+            results.push(result);
+        }
+
+        let table = comparison_table(&results);
+        match &self.options.output_path {
+            Some(output_path) => fs::write(output_path, table)?,
+            None => print!("{}", table),
+        }
+
+        Ok(())
+    }
+
+    fn agents(&self) -> Result<Vec<Box<dyn Agent>>, Box<dyn Error>> {
+        let seed = self.options.evaluation.config().seed;
+        let mut agents: Vec<Box<dyn Agent>> = vec![
+            Box::new(RandomAgent::new(seed)),
+            Box::new(GreedyScoreAgent::new(seed)),
+            Box::new(CornerAgent {}),
+        ];
+
+        for model_path in &self.options.model_paths {
+            let model =
+                model_storage::load_model_or_checkpoint::<B>(model_path, &Default::default())?;
+            agents.push(Box::new(ModelAgent::new(
+                model_path.display().to_string(),
+                model,
+                Default::default(),
+            )));
+        }
+
+        let depth = self.options.search_depth.unwrap_or(DEFAULT_SEARCH_DEPTH);
+        if depth > 0 {
+            agents.push(Box::new(SearchAgent::new(
+                format!("expectimax depth {}", depth),
+                Expectimax::new(HeuristicEvaluator::default(), depth),
+            )));
+        }
+
+        Ok(agents)
+    }
+}
@@ -0,0 +1,530 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::dqn::evaluator::EvaluationConfig;
+
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct EvaluationOptions {
+    pub num_games: Option<usize>,
+    pub epsilon: Option<f64>,
+    pub seed: Option<u64>,
+}
+
+impl EvaluationOptions {
+    pub(crate) fn config(&self) -> EvaluationConfig {
+        let defaults = EvaluationConfig::new();
+        EvaluationConfig {
+            num_games: self.num_games.unwrap_or(defaults.num_games),
+            epsilon: self.epsilon.unwrap_or(defaults.epsilon),
+            seed: self.seed.unwrap_or(defaults.seed),
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct HeadlessOptions {
+    pub max_epochs: Option<usize>,
+    pub time_limit: Option<Duration>,
+    pub load_path: Option<PathBuf>,
+    pub save_path: Option<PathBuf>,
+    pub checkpoint_directory: Option<PathBuf>,
+    pub resume_directory: Option<PathBuf>,
+    pub seed: Option<u64>,
+    pub config_path: Option<PathBuf>,
+    pub overrides: Vec<(String, String)>,
+    pub episodes_directory: Option<PathBuf>,
+    pub num_kept_episodes: Option<usize>,
+    pub metrics_directory: Option<PathBuf>,
+    pub tensorboard: bool,
+    pub evaluation_interval: Option<usize>,
+    pub evaluation: EvaluationOptions,
+}
+
+impl HeadlessOptions {
+    pub(crate) fn is_finished(&self, epochs: usize, elapsed: Duration) -> bool {
+        self.max_epochs
+            .is_some_and(|max_epochs| epochs >= max_epochs)
+            || self
+                .time_limit
+                .is_some_and(|time_limit| elapsed >= time_limit)
+    }
+
+    pub(crate) fn is_evaluation_due(&self, epochs: usize) -> bool {
+        self.evaluation_interval
+            .is_some_and(|interval| interval > 0 && epochs.is_multiple_of(interval))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct SearchOptions {
+    pub depth: usize,
+    // Leaves are evaluated with a heuristic when there's no model
+    pub model_path: Option<PathBuf>,
+    // The reward and discount factor the model was trained with
+    pub config_path: Option<PathBuf>,
+    pub overrides: Vec<(String, String)>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct BenchmarkOptions {
+    // Model files or checkpoint directories, each benchmarked as its own agent
+    pub model_paths: Vec<PathBuf>,
+    // 0 leaves out the search agent
+    pub search_depth: Option<usize>,
+    pub output_path: Option<PathBuf>,
+    pub evaluation: EvaluationOptions,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct WindowOptions {
+    // A checkpoint is saved here when quitting after training
+    pub autosave_directory: Option<PathBuf>,
+    // Hyperparameters shown in the settings until the first epoch runs
+    pub config_path: Option<PathBuf>,
+    pub overrides: Vec<(String, String)>,
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum CliCommand {
+    Window(WindowOptions),
+    Headless(HeadlessOptions),
+    Replay(PathBuf),
+    Evaluate(PathBuf, EvaluationOptions),
+    Search(SearchOptions, EvaluationOptions),
+    Benchmark(BenchmarkOptions),
+}
+
+impl CliCommand {
+    pub(crate) fn from_args(args: impl IntoIterator<Item = String>) -> Result<CliCommand, String> {
+        let mut args = args.into_iter();
+        let mut is_headless = false;
+        let mut replay_path = None;
+        let mut evaluated_model_path = None;
+        let mut search_depth = None;
+        let mut search_model_path = None;
+        let mut is_benchmark = false;
+        let mut benchmark = BenchmarkOptions::default();
+        let mut options = HeadlessOptions::default();
+        let mut window = WindowOptions::default();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--headless" => is_headless = true,
+                "--epochs" => {
+                    options.max_epochs = Some(parse_value(&arg, args.next())?);
+                }
+                "--time-limit" => {
+                    let seconds: u64 = parse_value(&arg, args.next())?;
+                    options.time_limit = Some(Duration::from_secs(seconds));
+                }
+                "--load" => {
+                    options.load_path = Some(parse_value(&arg, args.next())?);
+                }
+                "--save" => {
+                    options.save_path = Some(parse_value(&arg, args.next())?);
+                }
+                "--checkpoint" => {
+                    options.checkpoint_directory = Some(parse_value(&arg, args.next())?);
+                }
+                "--resume" => {
+                    options.resume_directory = Some(parse_value(&arg, args.next())?);
+                }
+                "--seed" => {
+                    options.seed = Some(parse_value(&arg, args.next())?);
+                }
+                "--config" => {
+                    options.config_path = Some(parse_value(&arg, args.next())?);
+                }
+                "--set" => {
+                    options.overrides.push(parse_override(&arg, args.next())?);
+                }
+                "--episodes-dir" => {
+                    options.episodes_directory = Some(parse_value(&arg, args.next())?);
+                }
+                "--keep-episodes" => {
+                    options.num_kept_episodes = Some(parse_value(&arg, args.next())?);
+                }
+                "--metrics-dir" => {
+                    options.metrics_directory = Some(parse_value(&arg, args.next())?);
+                }
+                "--tensorboard" => options.tensorboard = true,
+                "--eval-every" => {
+                    options.evaluation_interval = Some(parse_value(&arg, args.next())?);
+                }
+                "--eval-games" => {
+                    options.evaluation.num_games = Some(parse_value(&arg, args.next())?);
+                }
+                "--eval-epsilon" => {
+                    options.evaluation.epsilon = Some(parse_value(&arg, args.next())?);
+                }
+                "--eval-seed" => {
+                    options.evaluation.seed = Some(parse_value(&arg, args.next())?);
+                }
+                "--replay" => {
+                    replay_path = Some(parse_value(&arg, args.next())?);
+                }
+                "--evaluate" => {
+                    evaluated_model_path = Some(parse_value(&arg, args.next())?);
+                }
+                "--search" => {
+                    search_depth = Some(parse_value(&arg, args.next())?);
+                }
+                "--search-model" => {
+                    search_model_path = Some(parse_value(&arg, args.next())?);
+                }
+                "--benchmark" => is_benchmark = true,
+                "--benchmark-model" => {
+                    benchmark.model_paths.push(parse_value(&arg, args.next())?);
+                }
+                "--benchmark-search" => {
+                    benchmark.search_depth = Some(parse_value(&arg, args.next())?);
+                }
+                "--benchmark-output" => {
+                    benchmark.output_path = Some(parse_value(&arg, args.next())?);
+                }
+                "--autosave" => {
+                    window.autosave_directory = Some(parse_value(&arg, args.next())?);
+                }
+                _ => return Err(format!("Unknown argument: {}", arg)),
+            }
+        }
+
+        let num_commands = [
+            is_headless,
+            replay_path.is_some(),
+            evaluated_model_path.is_some(),
+            search_depth.is_some(),
+            is_benchmark,
+        ]
+        .into_iter()
+        .filter(|is_selected| *is_selected)
+        .count();
+        if num_commands > 1 {
+            return Err(
+                "Only one of --headless, --replay, --evaluate, --search and --benchmark can be used"
+                    .to_string(),
+            );
+        }
+        if search_model_path.is_some() && search_depth.is_none() {
+            return Err("--search-model requires --search".to_string());
+        }
+        let has_hyperparameters = options.config_path.is_some() || !options.overrides.is_empty();
+        if search_depth.is_some() && search_model_path.is_none() && has_hyperparameters {
+            return Err(
+                "--config and --set only apply to --search with --search-model".to_string(),
+            );
+        }
+        if options.tensorboard && options.metrics_directory.is_none() {
+            return Err("--tensorboard requires --metrics-dir".to_string());
+        }
+        if window != WindowOptions::default() && num_commands > 0 {
+            return Err("--autosave only applies to the window".to_string());
+        }
+        if benchmark != BenchmarkOptions::default() && !is_benchmark {
+            return Err("Benchmark options require --benchmark".to_string());
+        }
+
+        if let Some(replay_path) = replay_path {
+            if options != HeadlessOptions::default() {
+                return Err("--replay can't be combined with training options".to_string());
+            }
+            Ok(CliCommand::Replay(replay_path))
+        } else if let Some(model_path) = evaluated_model_path {
+            let evaluation = options.evaluation;
+            options.evaluation = EvaluationOptions::default();
+            if options != HeadlessOptions::default() {
+                return Err("--evaluate only accepts evaluation options".to_string());
+            }
+            Ok(CliCommand::Evaluate(model_path, evaluation))
+        } else if let Some(depth) = search_depth {
+            let evaluation = options.evaluation;
+            options.evaluation = EvaluationOptions::default();
+            let config_path = options.config_path.take();
+            let overrides = std::mem::take(&mut options.overrides);
+            if options != HeadlessOptions::default() {
+                return Err(
+                    "--search only accepts evaluation options, --config and --set".to_string(),
+                );
+            }
+            Ok(CliCommand::Search(
+                SearchOptions {
+                    depth,
+                    model_path: search_model_path,
+                    config_path,
+                    overrides,
+                },
+                evaluation,
+            ))
+        } else if is_benchmark {
+            // Every agent plays greedily, so random moves would only blur the comparison
+            if options.evaluation.epsilon.is_some() {
+                return Err("--benchmark doesn't accept --eval-epsilon".to_string());
+            }
+            benchmark.evaluation = options.evaluation;
+            options.evaluation = EvaluationOptions::default();
+            if options != HeadlessOptions::default() {
+                return Err("--benchmark only accepts benchmark and evaluation options".to_string());
+            }
+            Ok(CliCommand::Benchmark(benchmark))
+        } else if is_headless {
+            Ok(CliCommand::Headless(options))
+        } else {
+            window.config_path = options.config_path.take();
+            window.overrides = std::mem::take(&mut options.overrides);
+            if options != HeadlessOptions::default() {
+                return Err("Training options require --headless".to_string());
+            }
+            Ok(CliCommand::Window(window))
+        }
+    }
+}
+
+fn parse_value<T: std::str::FromStr>(name: &str, value: Option<String>) -> Result<T, String> {
+    let Some(value) = value else {
+        return Err(format!("Missing value for {}", name));
+    };
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value for {}: {}", name, value))
+}
+
+fn parse_override(name: &str, value: Option<String>) -> Result<(String, String), String> {
+    let value: String = parse_value(name, value)?;
+    match value.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!(
+            "Invalid value for {}: expected NAME=VALUE, got {}",
+            name, value
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn no_arguments_open_window() {
+        assert_eq!(
+            CliCommand::from_args(args(&[])),
+            Ok(CliCommand::Window(WindowOptions::default()))
+        );
+    }
+
+    #[test]
+    fn autosave_is_a_window_option() {
+        assert_eq!(
+            CliCommand::from_args(args(&["--autosave", "checkpoints"])),
+            Ok(CliCommand::Window(WindowOptions {
+                autosave_directory: Some(PathBuf::from("checkpoints")),
+                ..Default::default()
+            }))
+        );
+        assert!(CliCommand::from_args(args(&["--headless", "--autosave", "checkpoints"])).is_err());
+    }
+
+    #[test]
+    fn window_accepts_hyperparameters() {
+        assert_eq!(
+            CliCommand::from_args(args(&["--config", "hp.toml", "--set", "n_steps=3"])),
+            Ok(CliCommand::Window(WindowOptions {
+                config_path: Some(PathBuf::from("hp.toml")),
+                overrides: vec![("n_steps".to_string(), "3".to_string())],
+                ..Default::default()
+            }))
+        );
+        assert!(CliCommand::from_args(args(&["--config", "hp.toml", "--epochs", "5"])).is_err());
+    }
+
+    #[test]
+    fn headless_options_are_parsed() {
+        let command = CliCommand::from_args(args(&[
+            "--headless",
+            "--epochs",
+            "100",
+            "--time-limit",
+            "3600",
+            "--save",
+            "model.mpk",
+            "--seed",
+            "42",
+            "--config",
+            "hyperparameters.toml",
+            "--set",
+            "batch_size=512",
+            "--eval-every",
+            "500",
+        ]));
+
+        assert_eq!(
+            command,
+            Ok(CliCommand::Headless(HeadlessOptions {
+                max_epochs: Some(100),
+                time_limit: Some(Duration::from_secs(3600)),
+                save_path: Some(PathBuf::from("model.mpk")),
+                seed: Some(42),
+                config_path: Some(PathBuf::from("hyperparameters.toml")),
+                overrides: vec![("batch_size".to_string(), "512".to_string())],
+                evaluation_interval: Some(500),
+                ..Default::default()
+            }))
+        );
+    }
+
+    #[test]
+    fn checkpoint_options_are_parsed() {
+        assert_eq!(
+            CliCommand::from_args(args(&[
+                "--headless",
+                "--resume",
+                "run",
+                "--checkpoint",
+                "run"
+            ])),
+            Ok(CliCommand::Headless(HeadlessOptions {
+                checkpoint_directory: Some(PathBuf::from("run")),
+                resume_directory: Some(PathBuf::from("run")),
+                ..Default::default()
+            }))
+        );
+    }
+
+    #[test]
+    fn metrics_options_are_parsed() {
+        assert_eq!(
+            CliCommand::from_args(args(&[
+                "--headless",
+                "--metrics-dir",
+                "run",
+                "--tensorboard"
+            ])),
+            Ok(CliCommand::Headless(HeadlessOptions {
+                metrics_directory: Some(PathBuf::from("run")),
+                tensorboard: true,
+                ..Default::default()
+            }))
+        );
+        assert!(CliCommand::from_args(args(&["--headless", "--tensorboard"])).is_err());
+    }
+
+    #[test]
+    fn replay_is_parsed() {
+        assert_eq!(
+            CliCommand::from_args(args(&["--replay", "best.2048"])),
+            Ok(CliCommand::Replay(PathBuf::from("best.2048")))
+        );
+        assert!(CliCommand::from_args(args(&["--replay", "best.2048", "--headless"])).is_err());
+    }
+
+    #[test]
+    fn evaluate_is_parsed() {
+        assert_eq!(
+            CliCommand::from_args(args(&["--evaluate", "model.mpk", "--eval-games", "20"])),
+            Ok(CliCommand::Evaluate(
+                PathBuf::from("model.mpk"),
+                EvaluationOptions {
+                    num_games: Some(20),
+                    ..Default::default()
+                }
+            ))
+        );
+        assert!(
+            CliCommand::from_args(args(&["--evaluate", "model.mpk", "--epochs", "5"])).is_err()
+        );
+    }
+
+    #[test]
+    fn search_is_parsed() {
+        assert_eq!(
+            CliCommand::from_args(args(&["--search", "2", "--search-model", "model.mpk"])),
+            Ok(CliCommand::Search(
+                SearchOptions {
+                    depth: 2,
+                    model_path: Some(PathBuf::from("model.mpk")),
+                    config_path: None,
+                    overrides: Vec::new(),
+                },
+                EvaluationOptions::default()
+            ))
+        );
+        assert_eq!(
+            CliCommand::from_args(args(&[
+                "--search",
+                "2",
+                "--search-model",
+                "model.mpk",
+                "--set",
+                "discount_factor=0.95",
+            ])),
+            Ok(CliCommand::Search(
+                SearchOptions {
+                    depth: 2,
+                    model_path: Some(PathBuf::from("model.mpk")),
+                    config_path: None,
+                    overrides: vec![("discount_factor".to_string(), "0.95".to_string())],
+                },
+                EvaluationOptions::default()
+            ))
+        );
+        assert!(CliCommand::from_args(args(&["--search", "2", "--set", "n_steps=3"])).is_err());
+        assert!(CliCommand::from_args(args(&["--search-model", "model.mpk"])).is_err());
+        assert!(
+            CliCommand::from_args(args(&["--search", "2", "--evaluate", "model.mpk"])).is_err()
+        );
+    }
+
+    #[test]
+    fn benchmark_is_parsed() {
+        assert_eq!(
+            CliCommand::from_args(args(&[
+                "--benchmark",
+                "--benchmark-model",
+                "first.mpk",
+                "--benchmark-model",
+                "run",
+                "--benchmark-search",
+                "0",
+                "--eval-games",
+                "50",
+            ])),
+            Ok(CliCommand::Benchmark(BenchmarkOptions {
+                model_paths: vec![PathBuf::from("first.mpk"), PathBuf::from("run")],
+                search_depth: Some(0),
+                output_path: None,
+                evaluation: EvaluationOptions {
+                    num_games: Some(50),
+                    ..Default::default()
+                },
+            }))
+        );
+        assert!(CliCommand::from_args(args(&["--benchmark-model", "model.mpk"])).is_err());
+        assert!(CliCommand::from_args(args(&["--benchmark", "--eval-epsilon", "0.1"])).is_err());
+    }
+
+    #[test]
+    fn invalid_arguments_are_rejected() {
+        assert!(CliCommand::from_args(args(&["--headless", "--epochs"])).is_err());
+        assert!(CliCommand::from_args(args(&["--headless", "--epochs", "many"])).is_err());
+        assert!(CliCommand::from_args(args(&["--epochs", "10"])).is_err());
+        assert!(CliCommand::from_args(args(&["--fullscreen"])).is_err());
+        assert!(CliCommand::from_args(args(&["--headless", "--set", "batch_size"])).is_err());
+    }
+
+    #[test]
+    fn training_finishes_after_epochs_or_time_limit() {
+        let options = HeadlessOptions {
+            max_epochs: Some(10),
+            time_limit: Some(Duration::from_secs(60)),
+            ..Default::default()
+        };
+
+        assert!(!options.is_finished(9, Duration::from_secs(59)));
+        assert!(options.is_finished(10, Duration::from_secs(0)));
+        assert!(options.is_finished(1, Duration::from_secs(60)));
+        assert!(!HeadlessOptions::default().is_finished(1_000_000, Duration::MAX));
+    }
+}
@@ -0,0 +1,55 @@
+use std::error::Error;
+use std::path::PathBuf;
+
+use crate::game::{
+    board::{Board, NUM_COLUMNS, NUM_ROWS},
+    episode::Episode,
+    game_rng::GameRng,
+};
+
+pub(crate) struct EpisodeReplay {
+    file_path: PathBuf,
+}
+
+impl EpisodeReplay {
+    pub(crate) fn new(file_path: PathBuf) -> EpisodeReplay {
+        EpisodeReplay { file_path }
+    }
+
+    pub(crate) fn run(self) -> Result<(), Box<dyn Error>> {
+        let episode = Episode::load(&self.file_path)?;
+        let boards = episode.boards()?;
+
+        println!("initial board");
+        print_board(&boards[0]);
+        for (index, (step, board)) in episode.steps.iter().zip(&boards[1..]).enumerate() {
+            println!(
+                "step {}: {:?}, reward {:.3}, score {}",
+                index + 1,
+                step.direction,
+                step.reward,
+                board.score
+            );
+            print_board(board);
+        }
+        println!(
+            "final score {} after {} moves",
+            episode.final_score,
+            episode.steps.len()
+        );
+
+        Ok(())
+    }
+}
+
+fn print_board<R: GameRng>(board: &Board<R>) {
+    for row in 0..NUM_ROWS {
+        let line: Vec<String> = (0..NUM_COLUMNS)
+            .map(|column| match board.value_at(row, column) {
+                Some(value) => format!("{:>6}", value),
+                None => format!("{:>6}", "."),
+            })
+            .collect();
+        println!("{}", line.join(""));
+    }
+}
@@ -0,0 +1,119 @@
+use std::error::Error;
+use std::time::Instant;
+
+use burn::{module::AutodiffModule, tensor::backend::AutodiffBackend};
+
+use crate::{
+    cli::cli_options::HeadlessOptions,
+    dqn::hyperparameters::Hyperparameters,
+    training::{
+        episode_archive::EpisodeArchive,
+        evaluation_stats_recorder::{EvaluationStats, EvaluationStatsRecorder},
+        game_trainer::{GameTrainer, new_game_evaluator, new_game_model, new_game_trainer},
+        metrics_writer::MetricsWriter,
+        model_storage,
+    },
+};
+
+const DEFAULT_NUM_KEPT_EPISODES: usize = 10;
+
+pub(crate) struct HeadlessTraining<B: AutodiffBackend> {
+    options: HeadlessOptions,
+    hyperparameters: Hyperparameters,
+    trainer: GameTrainer<B>,
+}
+
+impl<B: AutodiffBackend> HeadlessTraining<B> {
+    pub(crate) fn new(options: HeadlessOptions) -> Result<HeadlessTraining<B>, Box<dyn Error>> {
+        let hyperparameters = match (&options.config_path, &options.resume_directory) {
+            (Some(file_path), _) => Hyperparameters::load_file(file_path)?,
+            (None, Some(directory)) => model_storage::load_checkpoint_hyperparameters(directory)?,
+            (None, None) => Hyperparameters::new(),
+        };
+        let mut hyperparameters = hyperparameters.with_overrides(&options.overrides)?;
+        if let Some(seed) = options.seed {
+            hyperparameters.seed = Some(seed);
+        }
+        hyperparameters.validate()?;
+        println!("Hyperparameters: {}", hyperparameters);
+        let episode_archive = match &options.episodes_directory {
+            Some(directory) => EpisodeArchive::new(
+                directory.clone(),
+                options
+                    .num_kept_episodes
+                    .unwrap_or(DEFAULT_NUM_KEPT_EPISODES),
+            ),
+            None => EpisodeArchive::default(),
+        };
+
+        Ok(HeadlessTraining {
+            options,
+            hyperparameters: hyperparameters.clone(),
+            trainer: new_game_trainer(hyperparameters, episode_archive),
+        })
+    }
+
+    pub(crate) fn run(mut self) -> Result<(), Box<dyn Error>> {
+        let device = Default::default();
+        let mut model = new_game_model(&self.hyperparameters, &device);
+        let mut stats_history = Vec::new();
+        if let Some(directory) = &self.options.resume_directory {
+            (model, stats_history) =
+                model_storage::load_checkpoint(directory, &mut self.trainer, &device)?;
+            println!(
+                "Resumed from {} after {} epochs",
+                directory.display(),
+                stats_history.len()
+            );
+        } else if let Some(file_path) = &self.options.load_path {
+            model = model_storage::load_model(file_path.clone(), &device)?;
+            self.trainer.set_model_architecture(model.config().architecture);
+            println!("Loaded model from {}", file_path.display());
+        }
+
+        let mut metrics_writer = match &self.options.metrics_directory {
+            Some(directory) => Some(MetricsWriter::new(
+                directory.clone(),
+                self.options.tensorboard,
+            )?),
+            None => None,
+        };
+
+        let start_time = Instant::now();
+        loop {
+            let (updated_model, stats) = self.trainer.run_epoch(model);
+            model = updated_model;
+            let stats = stats?;
+            println!("{}", stats);
+            if let Some(metrics_writer) = &mut metrics_writer {
+                metrics_writer.write(&stats)?;
+            }
+            stats_history.push(stats.clone());
+
+            if self.options.is_evaluation_due(stats.epochs) {
+                let evaluator = new_game_evaluator(self.options.evaluation.config());
+                let evaluation_stats: EvaluationStats =
+                    evaluator.evaluate::<EvaluationStatsRecorder>(&model.valid());
+                println!(
+                    "evaluation after epoch {}: {}",
+                    stats.epochs, evaluation_stats
+                );
+            }
+
+            if self.options.is_finished(stats.epochs, start_time.elapsed()) {
+                break;
+            }
+        }
+
+        if let Some(directory) = &self.options.checkpoint_directory {
+            model_storage::save_checkpoint(directory, &model, &self.trainer, &stats_history)?;
+            println!("Saved checkpoint to {}", directory.display());
+        }
+        if let Some(file_path) = self.options.save_path {
+            model_storage::save_model(&model, file_path.clone())?;
+            println!("Saved model to {}", file_path.display());
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,6 @@
+pub(crate) mod agent_benchmark;
+pub(crate) mod cli_options;
+pub(crate) mod episode_replay;
+pub(crate) mod headless_training;
+pub(crate) mod model_evaluation;
+pub(crate) mod search_evaluation;
@@ -0,0 +1,40 @@
+use std::error::Error;
+use std::marker::PhantomData;
+use std::path::PathBuf;
+
+use burn::prelude::Backend;
+
+use crate::{
+    cli::cli_options::EvaluationOptions,
+    training::{
+        evaluation_stats_recorder::{EvaluationStats, EvaluationStatsRecorder},
+        game_trainer::new_game_evaluator,
+        model_storage,
+    },
+};
+
+pub(crate) struct ModelEvaluation<B: Backend> {
+    model_path: PathBuf,
+    options: EvaluationOptions,
+    _backend: PhantomData<B>,
+}
+
+impl<B: Backend> ModelEvaluation<B> {
+    pub(crate) fn new(model_path: PathBuf, options: EvaluationOptions) -> ModelEvaluation<B> {
+        ModelEvaluation {
+            model_path,
+            options,
+            _backend: PhantomData,
+        }
+    }
+
+    pub(crate) fn run(self) -> Result<(), Box<dyn Error>> {
+        let model = model_storage::load_model::<B>(self.model_path.clone(), &Default::default())?;
+
+        let evaluator = new_game_evaluator(self.options.config());
+        let stats: EvaluationStats = evaluator.evaluate::<EvaluationStatsRecorder>(&model);
+        println!("{}: {}", self.model_path.display(), stats);
+
+        Ok(())
+    }
+}
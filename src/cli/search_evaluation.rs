@@ -0,0 +1,77 @@
+use std::error::Error;
+use std::marker::PhantomData;
+
+use burn::prelude::Backend;
+
+use crate::{
+    cli::cli_options::{EvaluationOptions, SearchOptions},
+    dqn::{hyperparameters::Hyperparameters, state::StateType},
+    search::{
+        expectimax::{Expectimax, LeafEvaluator},
+        heuristic_evaluator::HeuristicEvaluator,
+        model_evaluator::ModelEvaluator,
+    },
+    training::{
+        evaluation_stats_recorder::{EvaluationStats, EvaluationStatsRecorder},
+        game_trainer::new_game_evaluator,
+        model_storage,
+    },
+};
+
+// Plays the evaluation games with expectimax instead of the network's greedy policy
+pub(crate) struct SearchEvaluation<B: Backend> {
+    search: SearchOptions,
+    options: EvaluationOptions,
+    _backend: PhantomData<B>,
+}
+
+impl<B: Backend> SearchEvaluation<B> {
+    pub(crate) fn new(search: SearchOptions, options: EvaluationOptions) -> SearchEvaluation<B> {
+        SearchEvaluation {
+            search,
+            options,
+            _backend: PhantomData,
+        }
+    }
+
+    pub(crate) fn run(self) -> Result<(), Box<dyn Error>> {
+        let stats = match &self.search.model_path {
+            Some(model_path) => {
+                let model =
+                    model_storage::load_model::<B>(model_path.clone(), &Default::default())?;
+                let hyperparameters = Hyperparameters::load(
+                    self.search.config_path.as_deref(),
+                    &self.search.overrides,
+                )?;
+                self.evaluate(ModelEvaluator::new(
+                    model,
+                    &hyperparameters,
+                    Default::default(),
+                ))
+            }
+            None => self.evaluate(HeuristicEvaluator::default()),
+        };
+
+        let evaluator_name = match &self.search.model_path {
+            Some(model_path) => model_path.display().to_string(),
+            None => "heuristic".to_string(),
+        };
+        println!(
+            "Expectimax depth {} with {}: {}",
+            self.search.depth, evaluator_name, stats
+        );
+
+        Ok(())
+    }
+
+    fn evaluate(&self, leaf_evaluator: impl LeafEvaluator) -> EvaluationStats {
+        let search = Expectimax::new(leaf_evaluator, self.search.depth);
+        let evaluator = new_game_evaluator::<B>(self.options.config());
+
+        evaluator.evaluate_policy::<EvaluationStatsRecorder>(|state| {
+            search
+                .best_direction(state.pack())
+                .expect("Games that aren't over have a possible move")
+        })
+    }
+}
@@ -0,0 +1,281 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::mem;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender, TryRecvError};
+use std::thread::{self, JoinHandle};
+
+use burn::{prelude::Backend, tensor::Device};
+use rand::{Rng, SeedableRng, distr::uniform::SampleRange, rngs::StdRng, seq::IndexedRandom};
+
+use crate::dqn::{
+    critic::CriticType, hyperparameters::Hyperparameters, model::Model, played_game::PlayedGame,
+    policy, state::StateType,
+};
+
+// Self-play threads, each with its own copy of the model on the inference backend. Dropping the
+// pool stops the actors after their current move and waits for them.
+pub(crate) struct ActorPool<M, S: StateType> {
+    games: Receiver<PlayedGame<S>>,
+    models: Vec<Sender<M>>,
+    is_stopped: Arc<AtomicBool>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl<M: Clone, S: StateType> ActorPool<M, S> {
+    // Game numbers continue from `started_games`, so that epsilon keeps decaying after resuming
+    pub(crate) fn spawn<B, C>(
+        num_actors: usize,
+        model: M,
+        critic: C,
+        config: &Hyperparameters,
+        device: Device<B>,
+        started_games: usize,
+        rng: &mut StdRng,
+    ) -> Self
+    where
+        B: Backend,
+        M: Model<B> + 'static,
+        S: Send + 'static,
+        S::Action: Send,
+        C: CriticType<State = S> + Clone + Send + 'static,
+    {
+        // Full actors wait for the learner instead of piling up games, e.g. while paused
+        let (game_tx, game_rx) = mpsc::sync_channel(num_actors);
+        let started_games = Arc::new(AtomicUsize::new(started_games));
+        let is_stopped = Arc::new(AtomicBool::new(false));
+        let mut models = Vec::with_capacity(num_actors);
+        let mut threads = Vec::with_capacity(num_actors);
+
+        for _ in 0..num_actors {
+            let (model_tx, model_rx) = mpsc::channel();
+            models.push(model_tx);
+            let actor = Actor {
+                model: model.clone(),
+                models: model_rx,
+                games: game_tx.clone(),
+                critic: critic.clone(),
+                config: config.clone(),
+                device: device.clone(),
+                started_games: started_games.clone(),
+                is_stopped: is_stopped.clone(),
+                rng: StdRng::seed_from_u64(rng.random()),
+            };
+            threads.push(thread::spawn(move || actor.run()));
+        }
+
+        ActorPool {
+            games: game_rx,
+            models,
+            is_stopped,
+            threads,
+        }
+    }
+
+    pub(crate) fn sync_model(&self, model: &M) {
+        for models in &self.models {
+            let _ = models.send(model.clone());
+        }
+    }
+
+    pub(crate) fn try_next_game(&self) -> Result<Option<PlayedGame<S>>, ActorsStopped> {
+        match self.games.try_recv() {
+            Ok(game) => Ok(Some(game)),
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => Err(ActorsStopped),
+        }
+    }
+
+    pub(crate) fn next_game(&self) -> Result<PlayedGame<S>, ActorsStopped> {
+        self.games.recv().map_err(|_| ActorsStopped)
+    }
+}
+
+impl<M, S: StateType> Drop for ActorPool<M, S> {
+    fn drop(&mut self) {
+        self.is_stopped.store(true, Ordering::Relaxed);
+        // Actors waiting for room in the full game channel only return once its receiver is gone
+        let (_, closed_games) = mpsc::sync_channel(0);
+        drop(mem::replace(&mut self.games, closed_games));
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+}
+
+// Actors only stop on their own when they panic, the pool can't play any more games then
+#[derive(Debug)]
+pub(crate) struct ActorsStopped;
+
+impl Display for ActorsStopped {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "All self-play actors stopped")
+    }
+}
+
+impl Error for ActorsStopped {}
+
+struct Actor<B: Backend, M, S: StateType, C> {
+    model: M,
+    models: Receiver<M>,
+    games: SyncSender<PlayedGame<S>>,
+    critic: C,
+    config: Hyperparameters,
+    device: Device<B>,
+    started_games: Arc<AtomicUsize>,
+    is_stopped: Arc<AtomicBool>,
+    rng: StdRng,
+}
+
+impl<B, M, S, C> Actor<B, M, S, C>
+where
+    B: Backend,
+    M: Model<B>,
+    S: StateType,
+    C: CriticType<State = S>,
+{
+    fn run(mut self) {
+        while let Some(game) = self.play_game() {
+            if self.games.send(game).is_err() {
+                return;
+            }
+        }
+    }
+
+    // None when the pool stopped during the game
+    fn play_game(&mut self) -> Option<PlayedGame<S>> {
+        let game_num = self.started_games.fetch_add(1, Ordering::Relaxed) + 1;
+        let mut game = PlayedGame::<S>::new(self.rng.random(), self.config.epsilon(game_num));
+
+        while !game.current_state().is_terminal() {
+            if self.is_stopped.load(Ordering::Relaxed) {
+                return None;
+            }
+            self.receive_latest_model();
+            let state = game.current_state();
+            let (action, greedy) = self.pick_action(state, game.epsilon);
+            let next_state = state.advance(&action);
+            let reward_terms = self.critic.reward_terms(state, &action, &next_state);
+            if let Some(is_illegal) = greedy {
+                game.record_greedy_action(is_illegal);
+            }
+            game.record_step(action, reward_terms, next_state);
+        }
+
+        Some(game)
+    }
+
+    fn receive_latest_model(&mut self) {
+        while let Ok(model) = self.models.try_recv() {
+            self.model = model;
+        }
+    }
+
+    // Greedy actions come with whether the best one before masking was illegal
+    fn pick_action(&mut self, state: &S, epsilon: f64) -> (S::Action, Option<bool>) {
+        if (0.0..=1.0).sample_single(&mut self.rng).unwrap() <= epsilon {
+            let action = state
+                .possible_actions()
+                .choose(&mut self.rng)
+                .unwrap()
+                .clone();
+            (action, None)
+        } else {
+            let (action, is_illegal) = policy::best_legal_action(state, &self.model, &self.device);
+            (action, Some(is_illegal))
+        }
+    }
+}
+
+#[cfg(all(test, feature = "ndarray"))]
+mod tests {
+    use super::*;
+    use crate::dqn::model::tests::FixedOrderModel;
+    use crate::dqn::state::ActionType;
+    use crate::game::{board::Board, game_rng::RealGameRng};
+    use crate::training::training_critic::TrainingCritic;
+    use burn::backend::NdArray;
+
+    type TestState = Board<RealGameRng>;
+
+    // Hardly any random moves, but every game number still gets its own epsilon
+    fn test_config() -> Hyperparameters {
+        Hyperparameters::new()
+            .with_initial_epsilon(1e-6)
+            .with_min_epsilon(0.0)
+            .with_epsilon_decay_frames(1000)
+    }
+
+    fn spawn_actors(
+        model: FixedOrderModel<NdArray>,
+        started_games: usize,
+    ) -> ActorPool<FixedOrderModel<NdArray>, TestState> {
+        let config = test_config();
+        ActorPool::spawn::<NdArray, _>(
+            2,
+            model,
+            TrainingCritic::new(&config),
+            &config,
+            Default::default(),
+            started_games,
+            &mut StdRng::seed_from_u64(0),
+        )
+    }
+
+    fn most_played_action(game: &PlayedGame<TestState>) -> usize {
+        let mut counts = [0; 4];
+        for step in &game.steps {
+            counts[step.action.index()] += 1;
+        }
+        (0..4).max_by_key(|&index| counts[index]).unwrap()
+    }
+
+    #[test]
+    fn game_numbers_continue_from_the_started_games() {
+        let config = test_config();
+        let actors = spawn_actors(FixedOrderModel::new([3.0, 2.0, 1.0, 0.0]), 5);
+
+        let mut game_nums: Vec<usize> = (0..6)
+            .map(|_| {
+                let game = actors.next_game().unwrap();
+                assert!(!game.steps.is_empty());
+                (1..100)
+                    .find(|&game_num| config.epsilon(game_num) == game.epsilon)
+                    .unwrap()
+            })
+            .collect();
+        game_nums.sort();
+        game_nums.dedup();
+
+        assert_eq!(game_nums.len(), 6);
+        assert!(game_nums[0] > 5);
+    }
+
+    #[test]
+    fn dropping_the_pool_stops_the_actors() {
+        let actors = spawn_actors(FixedOrderModel::new([3.0, 2.0, 1.0, 0.0]), 0);
+        let is_stopped = actors.is_stopped.clone();
+        // By now the other actor may wait for room in the game channel
+        actors.next_game().unwrap();
+
+        drop(actors);
+
+        assert!(is_stopped.load(Ordering::Relaxed));
+        assert_eq!(Arc::strong_count(&is_stopped), 1);
+    }
+
+    #[test]
+    fn synced_models_replace_the_actor_models() {
+        let actors = spawn_actors(FixedOrderModel::new([3.0, 2.0, 1.0, 0.0]), 0);
+        assert_eq!(most_played_action(&actors.next_game().unwrap()), 0);
+
+        actors.sync_model(&FixedOrderModel::new([0.0, 1.0, 2.0, 3.0]));
+
+        // Games queued or under way before the sync still finish with the old model
+        let is_synced = (0..10)
+            .map(|_| actors.next_game().unwrap())
+            .any(|game| most_played_action(&game) == 3);
+        assert!(is_synced);
+    }
+}
@@ -0,0 +1,63 @@
+use std::fmt::{Display, Formatter};
+use std::io;
+
+use burn::config::{Config, ConfigError};
+use burn::record::RecorderError;
+
+use crate::training::model_storage::ModelError;
+
+pub(crate) const OPTIMIZER_FILE_NAME: &str = "optimizer";
+pub(crate) const TARGET_NETWORK_FILE_NAME: &str = "target_network";
+pub(crate) const PROGRESS_FILE_NAME: &str = "progress.json";
+pub(crate) const HYPERPARAMETERS_FILE_NAME: &str = "hyperparameters.json";
+
+#[derive(Config, Debug)]
+pub(crate) struct TrainerProgress {
+    pub epoch_num: usize,
+    pub frame_num: usize,
+}
+
+#[derive(Debug)]
+pub(crate) enum CheckpointError {
+    Io(io::Error),
+    Recorder(RecorderError),
+    Config(ConfigError),
+    Model(ModelError),
+}
+
+impl Display for CheckpointError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CheckpointError::Io(error) => write!(f, "Checkpoint IO error: {}", error),
+            CheckpointError::Recorder(error) => write!(f, "Checkpoint record error: {}", error),
+            CheckpointError::Config(error) => write!(f, "Checkpoint progress error: {}", error),
+            CheckpointError::Model(error) => write!(f, "Checkpoint model error: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for CheckpointError {}
+
+impl From<io::Error> for CheckpointError {
+    fn from(error: io::Error) -> Self {
+        CheckpointError::Io(error)
+    }
+}
+
+impl From<RecorderError> for CheckpointError {
+    fn from(error: RecorderError) -> Self {
+        CheckpointError::Recorder(error)
+    }
+}
+
+impl From<ConfigError> for CheckpointError {
+    fn from(error: ConfigError) -> Self {
+        CheckpointError::Config(error)
+    }
+}
+
+impl From<ModelError> for CheckpointError {
+    fn from(error: ModelError) -> Self {
+        CheckpointError::Model(error)
+    }
+}
@@ -3,10 +3,15 @@ use crate::dqn::state::StateType;
 pub(crate) trait CriticType {
     type State: StateType;
 
-    fn reward(
+    // Weighted parts of the reward by name, see total_reward()
+    fn reward_terms(
         &self,
         state: &Self::State,
         action: &<Self::State as StateType>::Action,
         next_state: &Self::State,
-    ) -> f32;
+    ) -> Vec<(&'static str, f32)>;
+}
+
+pub(crate) fn total_reward(reward_terms: &[(&'static str, f32)]) -> f32 {
+    reward_terms.iter().map(|(_, value)| value).sum()
 }
@@ -0,0 +1,14 @@
+use crate::dqn::state::StateType;
+
+pub(crate) trait EpisodeRecorderType {
+    type State: StateType;
+
+    fn record_initial_state(&mut self, state: &Self::State);
+    fn record_step(
+        &mut self,
+        action: &<Self::State as StateType>::Action,
+        reward: f32,
+        next_state: &Self::State,
+    );
+    fn record_final_state(&mut self, state: &Self::State);
+}
@@ -0,0 +1,92 @@
+use std::marker::PhantomData;
+
+use burn::{prelude::Backend, tensor::Device};
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::IndexedRandom};
+
+use crate::dqn::{model::Model, policy, state::StateType};
+
+pub(crate) trait EvaluationRecorderType: Default {
+    type Stats;
+    type State: StateType;
+
+    fn record_game(&mut self, final_state: &Self::State, game_length: u32);
+    fn stats(&self) -> Self::Stats;
+}
+
+pub(crate) struct EvaluationConfig {
+    pub num_games: usize,
+    pub epsilon: f64,
+    pub seed: u64,
+}
+
+impl EvaluationConfig {
+    pub(crate) fn new() -> Self {
+        EvaluationConfig {
+            num_games: 100,
+            epsilon: 0.0,
+            seed: 0,
+        }
+    }
+}
+
+// Plays games with a fixed set of seeds and a (mostly) greedy policy. Nothing is learned and
+// nothing is stored, so results of different models are directly comparable.
+pub(crate) struct Evaluator<B, M, S>
+where
+    B: Backend,
+    M: Model<B>,
+    S: StateType,
+{
+    config: EvaluationConfig,
+    device: Device<B>,
+    _model: PhantomData<M>,
+    _state: PhantomData<S>,
+}
+
+impl<B, M, S> Evaluator<B, M, S>
+where
+    B: Backend,
+    M: Model<B>,
+    S: StateType,
+{
+    pub fn new(config: EvaluationConfig, device: Device<B>) -> Evaluator<B, M, S> {
+        Evaluator {
+            config,
+            device,
+            _model: PhantomData,
+            _state: PhantomData,
+        }
+    }
+
+    pub fn evaluate<V: EvaluationRecorderType<State = S>>(&self, model: &M) -> V::Stats {
+        self.evaluate_policy::<V>(|state| policy::best_action(state, model, &self.device))
+    }
+
+    // Plays the same games with actions picked by something else than a model, e.g. a search
+    pub fn evaluate_policy<V: EvaluationRecorderType<State = S>>(
+        &self,
+        mut pick_action: impl FnMut(&S) -> S::Action,
+    ) -> V::Stats {
+        let mut rng = StdRng::seed_from_u64(self.config.seed);
+        let mut recorder = V::default();
+
+        for game in 0..self.config.num_games {
+            let mut state = S::initial_state_with_seed(self.config.seed.wrapping_add(game as u64));
+            let mut game_length = 0;
+
+            while !state.is_terminal() {
+                let action = if rng.random::<f64>() < self.config.epsilon {
+                    state.possible_actions().choose(&mut rng).unwrap().clone()
+                } else {
+                    pick_action(&state)
+                };
+                state = state.advance(&action);
+                game_length += 1;
+            }
+
+            recorder.record_game(&state, game_length);
+        }
+
+        recorder.stats()
+    }
+}
@@ -0,0 +1,39 @@
+use burn::{
+    Tensor,
+    module::{AutodiffModule, ModuleVisitor, Param},
+    optim::GradientsParams,
+    tensor::{Device, backend::AutodiffBackend},
+};
+
+// Global L2 norm over the gradients of every float parameter, kept on the device so that it can
+// be read together with other values
+pub(crate) fn gradient_norm<B, M>(
+    model: &M,
+    grads: &GradientsParams,
+    device: &Device<B>,
+) -> Tensor<B::InnerBackend, 1>
+where
+    B: AutodiffBackend,
+    M: AutodiffModule<B>,
+{
+    let mut visitor = GradientNormVisitor::<B> {
+        grads,
+        sum_of_squares: Tensor::zeros([1], device),
+    };
+    model.visit(&mut visitor);
+    visitor.sum_of_squares.sqrt()
+}
+
+struct GradientNormVisitor<'a, B: AutodiffBackend> {
+    grads: &'a GradientsParams,
+    sum_of_squares: Tensor<B::InnerBackend, 1>,
+}
+
+impl<B: AutodiffBackend> ModuleVisitor<B> for GradientNormVisitor<'_, B> {
+    fn visit_float<const D: usize>(&mut self, param: &Param<Tensor<B, D>>) {
+        if let Some(grad) = self.grads.get::<B::InnerBackend, D>(param.id) {
+            let squares = grad.powi_scalar(2).sum();
+            self.sum_of_squares = self.sum_of_squares.clone() + squares;
+        }
+    }
+}
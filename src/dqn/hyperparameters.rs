@@ -0,0 +1,401 @@
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use burn::config::Config;
+use serde_json::{Map, Value};
+
+use crate::training::game_model::ModelArchitecture;
+
+#[derive(Config, Debug)]
+pub(crate) struct Hyperparameters {
+    #[config(default = 0.00025)]
+    pub learning_rate: f32,
+    #[config(default = 0.99)]
+    pub discount_factor: f32,
+    #[config(default = "8 * 1024")]
+    pub batch_size: usize,
+    #[config(default = 15_000_000)]
+    pub replay_buffer_capacity: usize,
+    #[config(default = 0.5)]
+    pub initial_epsilon: f64,
+    #[config(default = 7500)]
+    pub epsilon_decay_frames: i32,
+    #[config(default = 0.0001)]
+    pub min_epsilon: f64,
+    #[config(default = 25)]
+    pub training_frequency: usize,
+    #[config(default = 10000)]
+    pub network_sync_frequency: usize,
+    #[config(default = 1)]
+    pub n_steps: usize,
+    #[config(default = false)]
+    pub prioritized_replay: bool,
+    #[config(default = 0.6)]
+    pub priority_alpha: f32,
+    #[config(default = 0.4)]
+    pub priority_beta: f32,
+    #[config(default = 1_000_000)]
+    pub priority_beta_frames: usize,
+    // Games are played by this many threads while the training thread only learns, 0 plays
+    // them on the training thread between training steps
+    #[config(default = 0)]
+    pub num_actors: usize,
+    // Training steps between copies of the learned weights to the actors
+    #[config(default = 10)]
+    pub actor_sync_frequency: usize,
+    // While waiting for actor games, the learner keeps training up to this many times the
+    // training steps earned by the moves received so far. 0 lets it train without a limit.
+    #[config(default = 2.0)]
+    pub actor_replay_ratio: f32,
+    // Games played side by side on the training thread, whose moves are picked with one
+    // batched forward pass
+    #[config(default = 1)]
+    pub num_envs: usize,
+    // Weights of the reward terms, see training::reward_terms. Losing only gives
+    // reward_game_over, whatever the other terms would add.
+    #[config(default = 0.0)]
+    pub reward_score_delta: f32,
+    #[config(default = 0.1)]
+    pub reward_log_score: f32,
+    #[config(default = 1.0)]
+    pub reward_empty_tiles: f32,
+    #[config(default = 0.0)]
+    pub reward_monotonicity: f32,
+    #[config(default = 0.0)]
+    pub reward_smoothness: f32,
+    #[config(default = 0.0)]
+    pub reward_max_tile_in_corner: f32,
+    #[config(default = 0.0)]
+    pub reward_merges: f32,
+    #[config(default = -10.0)]
+    pub reward_game_over: f32,
+    // mlp, conv, residual or ntuple. Only used for new models, loaded ones keep their own.
+    #[config(default = "ModelArchitecture::Mlp")]
+    pub model_architecture: ModelArchitecture,
+    pub seed: Option<u64>,
+}
+
+impl Hyperparameters {
+    // Decays linearly over the first epsilon_decay_frames epochs
+    pub(crate) fn epsilon(&self, epoch_num: usize) -> f64 {
+        f64::max(
+            self.min_epsilon,
+            self.initial_epsilon * (self.epsilon_decay_frames - epoch_num as i32) as f64
+                / self.epsilon_decay_frames as f64,
+        )
+    }
+
+    // Values missing from the file keep their defaults. The format is picked by the extension.
+    pub(crate) fn load_file(file_path: &Path) -> Result<Hyperparameters, HyperparametersError> {
+        let contents = fs::read_to_string(file_path)?;
+        let values = match file_path
+            .extension()
+            .and_then(|extension| extension.to_str())
+        {
+            Some("toml") => {
+                let table: toml::Table = toml::from_str(&contents)
+                    .map_err(|error| HyperparametersError::Parse(error.to_string()))?;
+                match serde_json::to_value(table) {
+                    Ok(Value::Object(values)) => values,
+                    _ => return Err(HyperparametersError::Parse("expected a table".to_string())),
+                }
+            }
+            Some("json") => serde_json::from_str(&contents)
+                .map_err(|error| HyperparametersError::Parse(error.to_string()))?,
+            _ => {
+                return Err(HyperparametersError::UnsupportedFormat(
+                    file_path.to_path_buf(),
+                ));
+            }
+        };
+
+        Hyperparameters::new().with_values(values)
+    }
+
+    // The config file, if any, with the overrides applied on top
+    pub(crate) fn load(
+        file_path: Option<&Path>,
+        overrides: &[(String, String)],
+    ) -> Result<Hyperparameters, HyperparametersError> {
+        let hyperparameters = match file_path {
+            Some(file_path) => Hyperparameters::load_file(file_path)?,
+            None => Hyperparameters::new(),
+        };
+        let hyperparameters = hyperparameters.with_overrides(overrides)?;
+        hyperparameters.validate()?;
+        Ok(hyperparameters)
+    }
+
+    // Values are read as JSON literals, an empty value clears optional hyperparameters.
+    pub(crate) fn with_overrides(
+        self,
+        overrides: &[(String, String)],
+    ) -> Result<Hyperparameters, HyperparametersError> {
+        let values = overrides
+            .iter()
+            .map(|(name, value)| {
+                let value = match value.trim() {
+                    "" => Value::Null,
+                    value => serde_json::from_str(value)
+                        .unwrap_or_else(|_| Value::String(value.to_string())),
+                };
+                (name.clone(), value)
+            })
+            .collect();
+
+        self.with_values(values)
+    }
+
+    pub(crate) fn values(&self) -> Vec<(String, String)> {
+        self.fields()
+            .into_iter()
+            .map(|(name, value)| match value {
+                Value::Null => (name, String::new()),
+                value => (name, value.to_string()),
+            })
+            .collect()
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), HyperparametersError> {
+        let mut problems = Vec::new();
+        if !(self.learning_rate > 0.0 && self.learning_rate.is_finite()) {
+            problems.push(format!(
+                "learning_rate must be positive, got {}",
+                self.learning_rate
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.discount_factor) {
+            problems.push(format!(
+                "discount_factor must be between 0 and 1, got {}",
+                self.discount_factor
+            ));
+        }
+        if self.batch_size == 0 {
+            problems.push("batch_size must be positive".to_string());
+        }
+        if self.replay_buffer_capacity < self.batch_size {
+            problems.push(format!(
+                "replay_buffer_capacity must hold at least one batch of {}, got {}",
+                self.batch_size, self.replay_buffer_capacity
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.initial_epsilon) {
+            problems.push(format!(
+                "initial_epsilon must be between 0 and 1, got {}",
+                self.initial_epsilon
+            ));
+        }
+        if !(0.0..=self.initial_epsilon).contains(&self.min_epsilon) {
+            problems.push(format!(
+                "min_epsilon must be between 0 and initial_epsilon, got {}",
+                self.min_epsilon
+            ));
+        }
+        if self.epsilon_decay_frames <= 0 {
+            problems.push(format!(
+                "epsilon_decay_frames must be positive, got {}",
+                self.epsilon_decay_frames
+            ));
+        }
+        if self.training_frequency == 0 {
+            problems.push("training_frequency must be positive".to_string());
+        }
+        if self.network_sync_frequency == 0 {
+            problems.push("network_sync_frequency must be positive".to_string());
+        }
+
+        if self.n_steps == 0 {
+            problems.push("n_steps must be positive".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.priority_alpha) {
+            problems.push(format!(
+                "priority_alpha must be between 0 and 1, got {}",
+                self.priority_alpha
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.priority_beta) {
+            problems.push(format!(
+                "priority_beta must be between 0 and 1, got {}",
+                self.priority_beta
+            ));
+        }
+        if self.priority_beta_frames == 0 {
+            problems.push("priority_beta_frames must be positive".to_string());
+        }
+        if self.actor_sync_frequency == 0 {
+            problems.push("actor_sync_frequency must be positive".to_string());
+        }
+        if self.actor_replay_ratio.is_nan() || self.actor_replay_ratio < 0.0 {
+            problems.push(format!(
+                "actor_replay_ratio can't be negative, got {}",
+                self.actor_replay_ratio
+            ));
+        }
+        if self.num_envs == 0 {
+            problems.push("num_envs must be positive".to_string());
+        }
+        if self.num_envs > 1 && self.num_actors > 0 {
+            problems.push("num_envs and num_actors can't be combined".to_string());
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(HyperparametersError::OutOfRange(problems))
+        }
+    }
+
+    fn with_values(
+        self,
+        values: Map<String, Value>,
+    ) -> Result<Hyperparameters, HyperparametersError> {
+        let mut hyperparameters = self;
+        for (name, value) in values {
+            let mut fields = hyperparameters.fields();
+            if fields.insert(name.clone(), value).is_none() {
+                return Err(HyperparametersError::UnknownName(name));
+            }
+            hyperparameters = serde_json::from_value(Value::Object(fields)).map_err(|error| {
+                HyperparametersError::InvalidValue {
+                    name,
+                    message: error.to_string(),
+                }
+            })?;
+        }
+
+        Ok(hyperparameters)
+    }
+
+    // Goes through a string so that f32 values keep their short representation.
+    fn fields(&self) -> Map<String, Value> {
+        let json = serde_json::to_string(self).expect("Hyperparameters should serialize");
+        serde_json::from_str(&json).expect("Serialized hyperparameters should parse")
+    }
+}
+
+#[derive(Debug)]
+pub(crate) enum HyperparametersError {
+    Io(io::Error),
+    UnsupportedFormat(PathBuf),
+    Parse(String),
+    UnknownName(String),
+    InvalidValue { name: String, message: String },
+    OutOfRange(Vec<String>),
+}
+
+impl Display for HyperparametersError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HyperparametersError::Io(error) => write!(f, "Hyperparameters IO error: {}", error),
+            HyperparametersError::UnsupportedFormat(file_path) => write!(
+                f,
+                "Unsupported hyperparameters file {}, expected .toml or .json",
+                file_path.display()
+            ),
+            HyperparametersError::Parse(message) => {
+                write!(f, "Invalid hyperparameters file: {}", message)
+            }
+            HyperparametersError::UnknownName(name) => {
+                write!(f, "Unknown hyperparameter: {}", name)
+            }
+            HyperparametersError::InvalidValue { name, message } => {
+                write!(f, "Invalid value for {}: {}", name, message)
+            }
+            HyperparametersError::OutOfRange(problems) => {
+                write!(f, "Invalid hyperparameters: {}", problems.join("; "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for HyperparametersError {}
+
+impl From<io::Error> for HyperparametersError {
+    fn from(error: io::Error) -> Self {
+        HyperparametersError::Io(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overrides(values: &[(&str, &str)]) -> Vec<(String, String)> {
+        values
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn partial_toml_file_keeps_defaults() {
+        let file_path = std::env::temp_dir().join(format!(
+            "dqn2048-{}-partial-toml-file-keeps-defaults.toml",
+            std::process::id()
+        ));
+        fs::write(
+            &file_path,
+            "learning_rate = 0.001\nbatch_size = 256\nseed = 7\n",
+        )
+        .unwrap();
+
+        let hyperparameters = Hyperparameters::load_file(&file_path).unwrap();
+        fs::remove_file(&file_path).unwrap();
+
+        assert_eq!(hyperparameters.learning_rate, 0.001);
+        assert_eq!(hyperparameters.batch_size, 256);
+        assert_eq!(hyperparameters.seed, Some(7));
+        assert_eq!(hyperparameters.discount_factor, 0.99);
+        assert!(hyperparameters.validate().is_ok());
+    }
+
+    #[test]
+    fn overrides_are_applied_by_name() {
+        let hyperparameters = Hyperparameters::new()
+            .with_seed(Some(3))
+            .with_overrides(&overrides(&[("min_epsilon", "0.01"), ("seed", "")]))
+            .unwrap();
+
+        assert_eq!(hyperparameters.min_epsilon, 0.01);
+        assert_eq!(hyperparameters.seed, None);
+    }
+
+    #[test]
+    fn invalid_values_are_reported_by_name() {
+        let unknown = Hyperparameters::new().with_overrides(&overrides(&[("lr", "0.1")]));
+        assert!(matches!(unknown, Err(HyperparametersError::UnknownName(name)) if name == "lr"));
+
+        let invalid = Hyperparameters::new().with_overrides(&overrides(&[("batch_size", "big")]));
+        assert!(matches!(
+            invalid,
+            Err(HyperparametersError::InvalidValue { name, .. }) if name == "batch_size"
+        ));
+    }
+
+    #[test]
+    fn out_of_range_values_fail_validation() {
+        let hyperparameters = Hyperparameters::new()
+            .with_discount_factor(1.5)
+            .with_replay_buffer_capacity(10);
+
+        let Err(HyperparametersError::OutOfRange(problems)) = hyperparameters.validate() else {
+            panic!("Validation should fail");
+        };
+        assert_eq!(problems.len(), 2);
+    }
+
+    #[test]
+    fn values_round_trip_through_overrides() {
+        let hyperparameters = Hyperparameters::new();
+
+        let restored = Hyperparameters::new()
+            .with_batch_size(1)
+            .with_overrides(&hyperparameters.values())
+            .unwrap();
+
+        assert_eq!(restored.values(), hyperparameters.values());
+        assert_eq!(restored.learning_rate, 0.00025);
+    }
+}
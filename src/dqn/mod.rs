@@ -1,8 +1,18 @@
+pub(crate) mod actors;
+pub(crate) mod checkpoint;
 pub(crate) mod critic;
+pub(crate) mod episode_recorder;
+pub(crate) mod evaluator;
+pub(crate) mod gradient_norm;
+pub(crate) mod hyperparameters;
 pub(crate) mod model;
+pub(crate) mod played_game;
+pub(crate) mod policy;
 pub(crate) mod replay_buffer;
 pub(crate) mod state;
 pub(crate) mod trainer;
 pub(crate) mod training_batch;
 pub(crate) mod stats;
+pub(crate) mod sum_tree;
 pub(crate) mod data_augmenter;
+pub(crate) mod vectorized_env;
@@ -7,4 +7,41 @@ use burn::{
 
 pub trait Model<B: Backend>: Module<B> {
     fn forward(&self, input: Tensor<B, 2, Float>) -> Tensor<B, 2, Float>;
+
+    // Estimated value of every state in the batch, shaped [batch_size, 1]. Models with a
+    // separate value stream can return it directly.
+    fn state_values(&self, input: Tensor<B, 2, Float>) -> Tensor<B, 2, Float> {
+        self.forward(input).max_dim(1)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use burn::module::Param;
+
+    // Ranks the actions in the same order whatever the input, so that tests know the greedy pick
+    #[derive(Module, Debug)]
+    pub(crate) struct FixedOrderModel<B: Backend> {
+        q_values: Param<Tensor<B, 1>>,
+    }
+
+    impl<B: Backend> FixedOrderModel<B> {
+        pub(crate) fn new(q_values: [f32; 4]) -> Self {
+            let q_values = Tensor::from_floats(q_values, &Default::default());
+            FixedOrderModel {
+                q_values: Param::from_tensor(q_values),
+            }
+        }
+    }
+
+    impl<B: Backend> Model<B> for FixedOrderModel<B> {
+        fn forward(&self, input: Tensor<B, 2>) -> Tensor<B, 2> {
+            let [batch_size, _] = input.dims();
+            self.q_values
+                .val()
+                .unsqueeze::<2>()
+                .repeat_dim(0, batch_size)
+        }
+    }
 }
@@ -0,0 +1,58 @@
+use crate::dqn::state::StateType;
+
+// A whole game played outside of the trainer's epoch loop. It's stored in the replay buffer in
+// one go once it's over, which keeps its steps together for the n-step returns.
+pub(crate) struct PlayedGame<S: StateType> {
+    pub game_seed: u64,
+    pub epsilon: f64,
+    pub initial_state: S,
+    pub steps: Vec<PlayedStep<S>>,
+    pub num_greedy_actions: u32,
+    pub num_illegal_greedy_actions: u32,
+}
+
+pub(crate) struct PlayedStep<S: StateType> {
+    pub action: S::Action,
+    pub reward_terms: Vec<(&'static str, f32)>,
+    pub next_state: S,
+}
+
+impl<S: StateType> PlayedGame<S> {
+    pub(crate) fn new(game_seed: u64, epsilon: f64) -> Self {
+        PlayedGame {
+            game_seed,
+            epsilon,
+            initial_state: S::initial_state_with_seed(game_seed),
+            steps: Vec::new(),
+            num_greedy_actions: 0,
+            num_illegal_greedy_actions: 0,
+        }
+    }
+
+    pub(crate) fn current_state(&self) -> &S {
+        self.steps
+            .last()
+            .map(|step| &step.next_state)
+            .unwrap_or(&self.initial_state)
+    }
+
+    pub(crate) fn record_greedy_action(&mut self, is_illegal: bool) {
+        self.num_greedy_actions += 1;
+        if is_illegal {
+            self.num_illegal_greedy_actions += 1;
+        }
+    }
+
+    pub(crate) fn record_step(
+        &mut self,
+        action: S::Action,
+        reward_terms: Vec<(&'static str, f32)>,
+        next_state: S,
+    ) {
+        self.steps.push(PlayedStep {
+            action,
+            reward_terms,
+            next_state,
+        });
+    }
+}
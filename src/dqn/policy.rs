@@ -0,0 +1,92 @@
+use burn::{
+    Tensor,
+    prelude::Backend,
+    tensor::{Device, TensorData},
+};
+
+use crate::dqn::{
+    model::Model,
+    state::{ActionType, StateType},
+};
+
+pub(crate) fn q_values<B, M, S>(state: &S, model: &M, device: &Device<B>) -> Vec<f32>
+where
+    B: Backend,
+    M: Model<B>,
+    S: StateType,
+{
+    let features = state.as_features();
+    let num_features = features.len();
+    let data = TensorData::new(features, [1, num_features]);
+    let input = Tensor::<B, 2>::from_data(data, device);
+    let output = model.forward(input);
+    output.into_data().into_vec().unwrap()
+}
+
+pub(crate) fn best_action<B, M, S>(state: &S, model: &M, device: &Device<B>) -> S::Action
+where
+    B: Backend,
+    M: Model<B>,
+    S: StateType,
+{
+    best_legal_action(state, model, device).0
+}
+
+// The legal action with the highest q-value, along with whether the highest q-value overall
+// belonged to an illegal action, i.e. whether masking the illegal actions changed the pick
+pub(crate) fn best_legal_action<B, M, S>(
+    state: &S,
+    model: &M,
+    device: &Device<B>,
+) -> (S::Action, bool)
+where
+    B: Backend,
+    M: Model<B>,
+    S: StateType,
+{
+    let output = q_values(state, model, device);
+    best_legal_action_for(state, &output)
+}
+
+// best_legal_action() for all the states with one forward pass, which costs about as much as one
+// for a single state
+pub(crate) fn best_legal_actions<B, M, S>(
+    states: &[&S],
+    model: &M,
+    device: &Device<B>,
+) -> Vec<(S::Action, bool)>
+where
+    B: Backend,
+    M: Model<B>,
+    S: StateType,
+{
+    if states.is_empty() {
+        return Vec::new();
+    }
+
+    let mut features = Vec::with_capacity(states.len() * S::NUM_FEATURES);
+    for state in states {
+        features.extend(state.as_features());
+    }
+    let data = TensorData::new(features, [states.len(), S::NUM_FEATURES]);
+    let input = Tensor::<B, 2>::from_data(data, device);
+    let output: Vec<f32> = model.forward(input).into_data().into_vec().unwrap();
+
+    states
+        .iter()
+        .zip(output.chunks(S::NUM_ACTIONS))
+        .map(|(state, q_values)| best_legal_action_for(*state, q_values))
+        .collect()
+}
+
+fn best_legal_action_for<S: StateType>(state: &S, q_values: &[f32]) -> (S::Action, bool) {
+    let best_action = state
+        .possible_actions()
+        .into_iter()
+        .max_by(|lhs, rhs| q_values[lhs.index()].total_cmp(&q_values[rhs.index()]))
+        .unwrap();
+    let best_q_value = q_values[best_action.index()];
+    let is_best_illegal = q_values.iter().any(|q_value| *q_value > best_q_value);
+
+    (best_action, is_best_illegal)
+}
@@ -1,76 +1,238 @@
-use std::collections::VecDeque;
 use burn::tensor::backend::AutodiffBackend;
 use rand::Rng;
-use rand::seq::IndexedRandom;
+use std::collections::VecDeque;
 
-use crate::dqn::{state::StateType, training_batch::TrainingBatch};
 use crate::dqn::data_augmenter::DataAugmenterType;
 use crate::dqn::state::ActionType;
+use crate::dqn::sum_tree::SumTree;
+use crate::dqn::{state::StateType, training_batch::TrainingBatch};
+
+// Keeps small TD errors from making transitions impossible to sample again.
+const MIN_PRIORITY: f64 = 1e-6;
 
 pub struct ReplayBuffer<S: StateType, D: DataAugmenterType<State = S>> {
     data_augmenter: D,
-    transitions: VecDeque<StateTransition>,
+    transitions: Vec<StateTransition<S>>,
     capacity: usize,
+    next_index: usize,
+    priorities: Option<Priorities>,
+    pending_steps: VecDeque<PendingStep<S>>,
+    n_steps: usize,
+    discount_factor: f32,
+}
+
+// A step whose n-step return isn't known yet
+struct PendingStep<S: StateType> {
+    state: S,
+    action: S::Action,
+    reward: f32,
+}
+
+// Proportional prioritization: transitions are sampled with probability p^alpha / sum(p^alpha)
+// where p is the last TD error seen for the transition.
+struct Priorities {
+    tree: SumTree,
+    alpha: f32,
+    max_priority: f64,
 }
 
 impl<S: StateType, D: DataAugmenterType<State = S>> ReplayBuffer<S, D> {
     pub fn new(data_augmenter: D, capacity: usize) -> Self {
         ReplayBuffer {
             data_augmenter,
-            transitions: VecDeque::new(),
+            transitions: Vec::new(),
             capacity,
+            next_index: 0,
+            priorities: None,
+            pending_steps: VecDeque::new(),
+            n_steps: 1,
+            discount_factor: 1.0,
         }
     }
 
+    pub fn new_prioritized(data_augmenter: D, capacity: usize, alpha: f32) -> Self {
+        ReplayBuffer {
+            priorities: Some(Priorities {
+                tree: SumTree::new(capacity),
+                alpha,
+                max_priority: 1.0,
+            }),
+            ..Self::new(data_augmenter, capacity)
+        }
+    }
+
+    // Stored transitions lead from a state to the one `n_steps` later, with the discounted sum
+    // of the rewards in between. The target has to bootstrap with discount_factor^n_steps.
+    pub fn with_n_step_returns(mut self, n_steps: usize, discount_factor: f32) -> Self {
+        self.n_steps = n_steps;
+        self.discount_factor = discount_factor;
+        self
+    }
+
     pub fn store(&mut self, state: S, action: S::Action, reward: f32, next_state: S) {
-        let new_transitions = self.data_augmenter.augment(state, action, reward, next_state);
+        self.pending_steps.push_back(PendingStep {
+            state,
+            action,
+            reward,
+        });
 
-        while self.transitions.len() + new_transitions.len() > self.capacity {
-            self.transitions.pop_front();
+        // Returns of the last steps of a game are truncated at the terminal state
+        if next_state.is_terminal() {
+            while !self.pending_steps.is_empty() {
+                self.store_pending_step(&next_state);
+            }
+        } else if self.pending_steps.len() >= self.n_steps {
+            self.store_pending_step(&next_state);
         }
+    }
 
-        self.transitions.extend(new_transitions);
+    fn store_pending_step(&mut self, next_state: &S) {
+        let discounted_return = self
+            .pending_steps
+            .iter()
+            .rev()
+            .fold(0.0, |future_return, step| {
+                step.reward + self.discount_factor * future_return
+            });
+        let Some(step) = self.pending_steps.pop_front() else {
+            return;
+        };
+
+        self.store_transition(
+            step.state,
+            step.action,
+            discounted_return,
+            next_state.clone(),
+        );
+    }
+
+    fn store_transition(&mut self, state: S, action: S::Action, reward: f32, next_state: S) {
+        let new_transitions = self
+            .data_augmenter
+            .augment(state, action, reward, next_state);
+
+        for transition in new_transitions {
+            if self.transitions.len() < self.capacity {
+                self.transitions.push(transition);
+            } else {
+                self.transitions[self.next_index] = transition;
+            }
+            // New transitions get the highest priority so that they're replayed at least once
+            if let Some(priorities) = &mut self.priorities {
+                priorities
+                    .tree
+                    .set(self.next_index, priorities.max_priority);
+            }
+            self.next_index = (self.next_index + 1) % self.capacity;
+        }
     }
 
     pub fn size(&self) -> usize {
         self.transitions.len()
     }
 
-    pub fn sample<B: AutodiffBackend>(&self, batch_size: usize) -> TrainingBatch<B> {
-        let mut rng = rand::rng();
-        let mut batch = Vec::with_capacity(batch_size);
-        for _ in 0..batch_size {
-            let index = rng.random_range(0..self.transitions.len());
-            batch.push(&self.transitions[index]);
+    pub fn is_prioritized(&self) -> bool {
+        self.priorities.is_some()
+    }
+
+    // `beta` controls how much of the prioritization bias is undone by the importance sampling
+    // weights. It's ignored by uniform sampling, where all the weights are 1.
+    pub fn sample<B: AutodiffBackend>(
+        &self,
+        batch_size: usize,
+        beta: f32,
+        rng: &mut impl Rng,
+    ) -> TrainingBatch<B> {
+        let Some(priorities) = &self.priorities else {
+            let indices: Vec<usize> = (0..batch_size)
+                .map(|_| rng.random_range(0..self.transitions.len()))
+                .collect();
+            return self.batch(indices, vec![1.0; batch_size]);
+        };
+
+        let total = priorities.tree.total();
+        let segment = total / batch_size as f64;
+        let indices: Vec<usize> = (0..batch_size)
+            .map(|i| {
+                let value = segment * (i as f64 + rng.random::<f64>());
+                priorities.tree.find(value).min(self.transitions.len() - 1)
+            })
+            .collect();
+
+        let num_transitions = self.transitions.len() as f64;
+        let weights: Vec<f64> = indices
+            .iter()
+            .map(|&index| {
+                let probability = priorities.tree.get(index) / total;
+                (num_transitions * probability).powf(-beta as f64)
+            })
+            .collect();
+        let max_weight = weights.iter().cloned().fold(f64::MIN_POSITIVE, f64::max);
+        let weights = weights
+            .into_iter()
+            .map(|weight| (weight / max_weight) as f32)
+            .collect();
+
+        self.batch(indices, weights)
+    }
+
+    pub fn update_priorities(&mut self, indices: &[usize], td_errors: &[f32]) {
+        let Some(priorities) = &mut self.priorities else {
+            return;
+        };
+
+        for (&index, &td_error) in indices.iter().zip(td_errors) {
+            let priority = (td_error.abs() as f64 + MIN_PRIORITY).powf(priorities.alpha as f64);
+            priorities.tree.set(index, priority);
+            priorities.max_priority = priorities.max_priority.max(priority);
         }
-        batch.into()
+    }
+
+    fn batch<B: AutodiffBackend>(
+        &self,
+        indices: Vec<usize>,
+        weights: Vec<f32>,
+    ) -> TrainingBatch<B> {
+        let records = indices
+            .iter()
+            .map(|&index| &self.transitions[index])
+            .collect();
+        TrainingBatch::new(records, indices, weights)
     }
 }
 
-#[derive(Clone, PartialEq, Debug)]
-pub struct StateTransition {
-    pub state: Vec<f32>,
-    pub action: usize,
+#[derive(Clone)]
+pub struct StateTransition<S: StateType> {
+    pub state: S::Packed,
+    pub action: u8,
     pub reward: f32,
-    pub next_state: Vec<f32>,
-    pub invalid_actions_mask: Vec<bool>,
-    pub is_terminal: f32,
+    pub next_state: S::Packed,
+    pub invalid_actions_mask: u16,
+    pub is_terminal: bool,
 }
 
-impl StateTransition {
-    pub fn new<S: StateType>(state: S, action: S::Action, reward: f32, next_state: S) -> Self {
-        let mut invalid_actions = vec![true; S::NUM_ACTIONS];
+impl<S: StateType> StateTransition<S> {
+    pub fn new(state: S, action: S::Action, reward: f32, next_state: S) -> Self {
+        assert!(
+            S::NUM_ACTIONS <= u16::BITS as usize,
+            "Too many actions for the mask"
+        );
+        let mut valid_actions_mask = 0u16;
         for valid_action in next_state.possible_actions() {
-            invalid_actions[valid_action.index() as usize] = false;
+            valid_actions_mask |= 1 << valid_action.index();
         }
 
         Self {
-            state: state.as_features(),
-            action: action.index(),
+            state: state.pack(),
+            action: action.index() as u8,
             reward,
-            next_state: next_state.as_features(),
-            invalid_actions_mask: invalid_actions,
-            is_terminal: if next_state.is_terminal() { 1.0 } else { 0.0 },
+            next_state: next_state.pack(),
+            invalid_actions_mask: !valid_actions_mask,
+            is_terminal: next_state.is_terminal(),
         }
     }
+
+    pub fn is_action_invalid(&self, index: usize) -> bool {
+        self.invalid_actions_mask & (1 << index) != 0
+    }
 }
@@ -4,13 +4,18 @@ pub trait ActionType: Clone {
 
 pub trait StateType: Clone {
     type Action: ActionType;
+    // Compact form kept in the replay buffer, expanded to features only for training batches
+    type Packed: Clone;
 
     const NUM_ACTIONS: usize;
     const NUM_FEATURES: usize;
 
     fn initial_state() -> Self;
+    fn initial_state_with_seed(seed: u64) -> Self;
     fn possible_actions(&self) -> Vec<Self::Action>;
     fn advance(&self, action: &Self::Action) -> Self;
     fn is_terminal(&self) -> bool;
     fn as_features(&self) -> Vec<f32>;
+    fn pack(&self) -> Self::Packed;
+    fn extend_with_features(packed: &Self::Packed, features: &mut Vec<f32>);
 }
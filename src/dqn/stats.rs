@@ -1,11 +1,90 @@
+use burn::{Tensor, prelude::Backend};
+use std::mem;
+
 use crate::dqn::state::StateType;
 
+// The training steps of an epoch, with the per step values summed up
+pub(crate) struct TrainingStepStats {
+    pub num_steps: usize,
+    pub loss_sum: f32,
+    pub q_value_sum: f32,
+    pub max_q_value: f32,
+    pub td_error_sum: f32,
+    pub gradient_norm_sum: f32,
+}
+
+// Adds up the values of every training step on the device. Reading them back waits for the
+// device, so it's only done once per epoch.
+pub(crate) struct TrainingStepSums<B: Backend> {
+    num_steps: usize,
+    // Loss, mean q-value, mean absolute td error and gradient norm
+    sums: Option<Tensor<B, 1>>,
+    max_q_value: Option<Tensor<B, 1>>,
+}
+
+impl<B: Backend> TrainingStepSums<B> {
+    pub(crate) fn new() -> Self {
+        TrainingStepSums {
+            num_steps: 0,
+            sums: None,
+            max_q_value: None,
+        }
+    }
+
+    // Every tensor holds a single value
+    pub(crate) fn add(
+        &mut self,
+        loss: Tensor<B, 1>,
+        mean_q_value: Tensor<B, 1>,
+        max_q_value: Tensor<B, 1>,
+        mean_td_error: Tensor<B, 1>,
+        gradient_norm: Tensor<B, 1>,
+    ) {
+        let values = Tensor::cat(vec![loss, mean_q_value, mean_td_error, gradient_norm], 0);
+        self.sums = Some(match self.sums.take() {
+            Some(sums) => sums + values,
+            None => values,
+        });
+        self.max_q_value = Some(match self.max_q_value.take() {
+            Some(max) => max.max_pair(max_q_value),
+            None => max_q_value,
+        });
+        self.num_steps += 1;
+    }
+
+    // None if there was no training step since the last call
+    pub(crate) fn take(&mut self) -> Option<TrainingStepStats> {
+        let (Some(sums), Some(max_q_value)) = (self.sums.take(), self.max_q_value.take()) else {
+            return None;
+        };
+        let values: Vec<f32> = Tensor::cat(vec![sums, max_q_value], 0)
+            .into_data()
+            .to_vec()
+            .unwrap();
+
+        Some(TrainingStepStats {
+            num_steps: mem::take(&mut self.num_steps),
+            loss_sum: values[0],
+            q_value_sum: values[1],
+            max_q_value: values[4],
+            td_error_sum: values[2],
+            gradient_norm_sum: values[3],
+        })
+    }
+}
+
 pub(crate) trait StatsRecorderType: Default {
     type Stats;
     type State: StateType;
 
     fn record_new_epoch(&mut self);
+    fn restore_epochs(&mut self, epochs: usize);
+    fn record_game_seed(&mut self, seed: u64);
     fn record_reward(&mut self, reward: f32);
+    fn record_reward_terms(&mut self, reward_terms: &[(&'static str, f32)]);
+    fn record_training_steps(&mut self, stats: &TrainingStepStats);
+    // Illegal greedy actions had the highest q-value before the illegal actions were masked
+    fn record_greedy_actions(&mut self, num_actions: u32, num_illegal: u32);
     fn record_final_state(&mut self, state: &Self::State, epoch_length: u32);
     fn record_replay_buffer_size(&mut self, size: usize);
     fn record_epsilon(&mut self, epsilon: f64);
@@ -0,0 +1,72 @@
+// Binary tree where every node holds the sum of its children, so that leaves can be sampled
+// proportionally to their values in O(log n).
+pub struct SumTree {
+    nodes: Vec<f64>,
+    num_leaves: usize,
+}
+
+impl SumTree {
+    pub fn new(capacity: usize) -> Self {
+        let num_leaves = capacity.max(1).next_power_of_two();
+        SumTree {
+            nodes: vec![0.0; 2 * num_leaves],
+            num_leaves,
+        }
+    }
+
+    pub fn total(&self) -> f64 {
+        self.nodes[1]
+    }
+
+    pub fn get(&self, index: usize) -> f64 {
+        self.nodes[self.num_leaves + index]
+    }
+
+    pub fn set(&mut self, index: usize, value: f64) {
+        let mut node = self.num_leaves + index;
+        let change = value - self.nodes[node];
+        while node > 0 {
+            self.nodes[node] += change;
+            node /= 2;
+        }
+    }
+
+    // Returns the leaf whose range of cumulative sums contains `value`.
+    pub fn find(&self, value: f64) -> usize {
+        let mut value = value.clamp(0.0, self.total());
+        let mut node = 1;
+        while node < self.num_leaves {
+            let left = 2 * node;
+            if value < self.nodes[left] || self.nodes[left + 1] <= 0.0 {
+                node = left;
+            } else {
+                value -= self.nodes[left];
+                node = left + 1;
+            }
+        }
+        node - self.num_leaves
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leaves_are_found_by_cumulative_sum() {
+        let mut tree = SumTree::new(5);
+        tree.set(0, 1.0);
+        tree.set(1, 2.0);
+        tree.set(3, 3.0);
+        tree.set(4, 4.0);
+        tree.set(4, 0.5);
+
+        assert_eq!(tree.total(), 6.5);
+        assert_eq!(tree.find(0.5), 0);
+        assert_eq!(tree.find(1.0), 1);
+        assert_eq!(tree.find(2.9), 1);
+        assert_eq!(tree.find(3.0), 3);
+        assert_eq!(tree.find(6.2), 4);
+        assert_eq!(tree.find(100.0), 4);
+    }
+}
@@ -1,52 +1,42 @@
+use crate::dqn::actors::{ActorPool, ActorsStopped};
+use crate::dqn::checkpoint::{
+    CheckpointError, HYPERPARAMETERS_FILE_NAME, OPTIMIZER_FILE_NAME, PROGRESS_FILE_NAME,
+    TARGET_NETWORK_FILE_NAME, TrainerProgress,
+};
 use crate::dqn::data_augmenter::DataAugmenterType;
-use crate::dqn::stats::StatsRecorderType;
+use crate::dqn::episode_recorder::EpisodeRecorderType;
+use crate::dqn::gradient_norm::gradient_norm;
+use crate::dqn::hyperparameters::Hyperparameters;
+use crate::dqn::played_game::PlayedGame;
+use crate::dqn::policy;
+use crate::dqn::stats::{StatsRecorderType, TrainingStepSums};
+use crate::dqn::vectorized_env::VectorizedEnv;
 use crate::dqn::{
-    critic::CriticType,
+    critic::{CriticType, total_reward},
     model::Model,
     replay_buffer::ReplayBuffer,
-    state::{ActionType, StateType},
+    state::StateType,
 };
+use crate::training::game_model::ModelArchitecture;
 use burn::tensor::Int;
 use burn::{
+    config::Config,
     module::AutodiffModule,
-    nn::loss::{HuberLossConfig, Reduction::Auto},
+    nn::loss::HuberLossConfig,
     optim::{adaptor::OptimizerAdaptor, Adam, AdamConfig, GradientsParams, Optimizer},
-    tensor::{backend::AutodiffBackend, Device, TensorData},
+    record::{DefaultFileRecorder, FullPrecisionSettings, Recorder},
+    tensor::{backend::AutodiffBackend, Device},
     Tensor,
 };
-use rand::{distr::uniform::SampleRange, rng, rngs::ThreadRng, seq::IndexedRandom};
-use std::cmp::Ordering;
+use rand::{
+    Rng, SeedableRng, distr::uniform::SampleRange, rngs::StdRng, seq::IndexedRandom,
+};
 use std::default::Default;
+use std::fs;
+use std::mem;
+use std::path::Path;
 
-pub(crate) struct Hyperparameters {
-    pub learning_rate: f32,
-    pub discount_factor: f32,
-    pub batch_size: usize,
-    pub replay_buffer_capacity: usize,
-    pub initial_epsilon: f64,
-    pub epsilon_decay_frames: i32,
-    pub min_epsilon: f64,
-    pub training_frequency: usize,
-    pub network_sync_frequency: usize,
-}
-
-impl Hyperparameters {
-    pub(crate) fn new() -> Self {
-        Hyperparameters {
-            learning_rate: 0.00025,
-            discount_factor: 0.99,
-            batch_size: 8 * 1024,
-            replay_buffer_capacity: 15_000_000,
-            initial_epsilon: 0.5,
-            epsilon_decay_frames: 7500,
-            min_epsilon: 0.0001,
-            training_frequency: 25,
-            network_sync_frequency: 10000,
-        }
-    }
-}
-
-pub(crate) struct Trainer<B, M, S, C, R, D>
+pub(crate) struct Trainer<B, M, S, C, R, D, E>
 where
     B: AutodiffBackend,
     M: Model<B> + AutodiffModule<B>,
@@ -55,6 +45,7 @@ where
     C: CriticType<State = S>,
     R: StatsRecorderType<State = S>,
     D: DataAugmenterType<State = S>,
+    E: EpisodeRecorderType<State = S>,
 {
     config: Hyperparameters,
     critic: C,
@@ -62,54 +53,155 @@ where
     optimizer: OptimizerAdaptor<Adam, M, B>,
     device: Device<B>,
     stats_recorder: R,
+    episode_recorder: E,
     epoch_num: usize,
     frame_num: usize,
     target_network: Option<M>,
+    actors: Option<ActorPool<M::InnerModule, S>>,
+    envs: Option<VectorizedEnv<S>>,
+    // Training steps earned by the frames of actor or vectorized games, but not done yet
+    pending_training_steps: usize,
+    // All the steps earned so far, which actor_replay_ratio is relative to
+    earned_training_steps: usize,
+    training_steps: usize,
+    training_step_sums: TrainingStepSums<B::InnerBackend>,
+    rng: StdRng,
 }
 
-impl<B, M, S, C, R, D> Trainer<B, M, S, C, R, D>
+impl<B, M, S, C, R, D, E> Trainer<B, M, S, C, R, D, E>
 where
     B: AutodiffBackend,
     M: Model<B> + AutodiffModule<B>,
-    M::InnerModule: Model<<B as AutodiffBackend>::InnerBackend>,
-    S: StateType,
-    C: CriticType<State = S>,
+    M::InnerModule: Model<<B as AutodiffBackend>::InnerBackend> + 'static,
+    S: StateType + Send + 'static,
+    S::Action: Send,
+    C: CriticType<State = S> + Clone + Send + 'static,
     R: StatsRecorderType<State = S>,
     D: DataAugmenterType<State = S>,
+    E: EpisodeRecorderType<State = S>,
 {
     pub fn new(
         config: Hyperparameters,
         critic: C,
         data_augmenter: D,
+        episode_recorder: E,
         device: Device<B>,
-    ) -> Trainer<B, M, S, C, R, D> {
-        let replay_buffer_capacity = config.replay_buffer_capacity;
+    ) -> Trainer<B, M, S, C, R, D, E> {
+        let replay_buffer = if config.prioritized_replay {
+            ReplayBuffer::new_prioritized(
+                data_augmenter,
+                config.replay_buffer_capacity,
+                config.priority_alpha,
+            )
+        } else {
+            ReplayBuffer::new(data_augmenter, config.replay_buffer_capacity)
+        }
+        .with_n_step_returns(config.n_steps, config.discount_factor);
+        let rng = match config.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_rng(&mut rand::rng()),
+        };
 
         Trainer {
             config,
             critic: critic,
-            replay_buffer: ReplayBuffer::new(data_augmenter, replay_buffer_capacity),
+            replay_buffer,
             optimizer: AdamConfig::new().init(),
             device: device,
             stats_recorder: Default::default(),
+            episode_recorder,
             epoch_num: 0,
             frame_num: 0,
             target_network: None,
+            actors: None,
+            envs: None,
+            pending_training_steps: 0,
+            earned_training_steps: 0,
+            training_steps: 0,
+            training_step_sums: TrainingStepSums::new(),
+            rng,
+        }
+    }
+
+    // Leaves out the replay buffer, with its priorities and the steps still waiting for their
+    // n-step returns, so training after a resume differs from an uninterrupted run
+    pub fn save_checkpoint(&self, directory: &Path, model: &M) -> Result<(), CheckpointError> {
+        let recorder = DefaultFileRecorder::<FullPrecisionSettings>::new();
+        fs::create_dir_all(directory)?;
+
+        recorder.record(
+            self.optimizer.to_record(),
+            directory.join(OPTIMIZER_FILE_NAME),
+        )?;
+        self.target_network
+            .as_ref()
+            .unwrap_or(model)
+            .clone()
+            .save_file(directory.join(TARGET_NETWORK_FILE_NAME), &recorder)?;
+        TrainerProgress::new(self.epoch_num, self.frame_num)
+            .save(directory.join(PROGRESS_FILE_NAME))?;
+        self.config.save(directory.join(HYPERPARAMETERS_FILE_NAME))?;
+
+        Ok(())
+    }
+
+    pub fn load_checkpoint(&mut self, directory: &Path, model: &M) -> Result<(), CheckpointError> {
+        let recorder = DefaultFileRecorder::<FullPrecisionSettings>::new();
+
+        let optimizer_record = recorder.load(directory.join(OPTIMIZER_FILE_NAME), &self.device)?;
+        let target_network = model.clone().load_file(
+            directory.join(TARGET_NETWORK_FILE_NAME),
+            &recorder,
+            &self.device,
+        )?;
+        let progress = TrainerProgress::load(directory.join(PROGRESS_FILE_NAME))?;
+
+        let optimizer = mem::replace(&mut self.optimizer, AdamConfig::new().init());
+        self.optimizer = optimizer.load_record(optimizer_record);
+        self.target_network = Some(target_network);
+        self.epoch_num = progress.epoch_num;
+        self.frame_num = progress.frame_num;
+        self.stats_recorder.restore_epochs(progress.epoch_num);
+
+        Ok(())
+    }
+
+    pub fn config(&self) -> &Hyperparameters {
+        &self.config
+    }
+
+    // Only new models are built with it, which includes the checkpoints saved later on
+    pub fn set_model_architecture(&mut self, architecture: ModelArchitecture) {
+        self.config.model_architecture = architecture;
+    }
+
+    // For a model loaded between epochs. The actors and the target network would otherwise keep
+    // the previous model, which might not even have the same architecture.
+    pub fn replace_model(&mut self, model: &M) {
+        self.target_network = None;
+        if let Some(actors) = &self.actors {
+            actors.sync_model(&model.valid());
         }
     }
 
-    pub fn run_epoch(&mut self, mut model: M) -> (M, R::Stats) {
+    // The model comes back even if the epoch failed, only actor epochs can fail
+    pub fn run_epoch(&mut self, mut model: M) -> (M, Result<R::Stats, ActorsStopped>) {
+        self.reseed_for_epoch();
+        if self.config.num_actors > 0 {
+            return self.run_actor_epoch(model);
+        }
+        if self.config.num_envs > 1 {
+            let (model, stats) = self.run_vectorized_epoch(model);
+            return (model, Ok(stats));
+        }
+
         // Epoch initialization
 
-        let mut state = S::initial_state();
+        let game_seed = self.rng.random();
+        let mut state = S::initial_state_with_seed(game_seed);
         let mut epoch_frames = 0;
         self.epoch_num += 1;
-        let epsilon = f64::max(
-            self.config.min_epsilon,
-            self.config.initial_epsilon
-                * (self.config.epsilon_decay_frames - self.epoch_num as i32) as f64
-                / self.config.epsilon_decay_frames as f64,
-        );
+        let epsilon = self.config.epsilon(self.epoch_num);
         if self.target_network.is_none() {
             self.target_network = Some(model.clone());
         }
@@ -117,16 +209,21 @@ where
         // Epoch loop
 
         self.stats_recorder.record_new_epoch();
+        self.stats_recorder.record_game_seed(game_seed);
+        self.episode_recorder.record_initial_state(&state);
         self.stats_recorder.record_epsilon(epsilon);
         while !state.is_terminal() {
             self.frame_num += 1;
             let action = self.pick_action(&state, &model, epsilon);
             let next_state = state.advance(&action);
-            let reward = self.critic.reward(&state, &action, &next_state);
+            let reward_terms = self.critic.reward_terms(&state, &action, &next_state);
+            let reward = total_reward(&reward_terms);
+            self.episode_recorder.record_step(&action, reward, &next_state);
             self.replay_buffer
                 .store(state, action, reward, next_state.clone());
             state = next_state;
             self.stats_recorder.record_reward(reward);
+            self.stats_recorder.record_reward_terms(&reward_terms);
             epoch_frames += 1;
 
             if self.replay_buffer.size() >= self.config.batch_size
@@ -139,12 +236,178 @@ where
             }
         }
         self.stats_recorder.record_final_state(&state, epoch_frames);
+        self.episode_recorder.record_final_state(&state);
         self.stats_recorder
             .record_replay_buffer_size(self.replay_buffer.size());
 
-        let stats = self.stats_recorder.stats();
+        let stats = self.epoch_stats();
 
-        (model, stats)
+        (model, Ok(stats))
+    }
+
+    // The epoch is the next game finished by any actor. While waiting for it, the learner keeps
+    // training as long as actor_replay_ratio allows, and only blocks once it's ahead of the actors.
+    fn run_actor_epoch(&mut self, mut model: M) -> (M, Result<R::Stats, ActorsStopped>) {
+        if self.target_network.is_none() {
+            self.target_network = Some(model.clone());
+        }
+        if self.actors.is_none() {
+            self.actors = Some(ActorPool::spawn(
+                self.config.num_actors,
+                model.valid(),
+                self.critic.clone(),
+                &self.config,
+                self.device.clone(),
+                self.epoch_num,
+                &mut self.rng,
+            ));
+        }
+
+        // Started before the wait, so that the training steps made meanwhile count for this epoch
+        self.stats_recorder.record_new_epoch();
+        let game = loop {
+            let actors = self.actors.as_ref().expect("Actors should've been spawned");
+            match actors.try_next_game() {
+                Ok(Some(game)) => break Ok(game),
+                Ok(None) if !self.is_actor_training_step_allowed() => break actors.next_game(),
+                Ok(None) => model = self.pending_training_step(model),
+                Err(error) => break Err(error),
+            }
+        };
+        let game = match game {
+            Ok(game) => game,
+            Err(error) => {
+                // A new pool is spawned if training goes on
+                self.actors = None;
+                return (model, Err(error));
+            }
+        };
+        self.store_played_game(game, &model);
+
+        (model, Ok(self.epoch_stats()))
+    }
+
+    // The epoch is the next game to finish among the ones played side by side
+    fn run_vectorized_epoch(&mut self, mut model: M) -> (M, R::Stats) {
+        if self.target_network.is_none() {
+            self.target_network = Some(model.clone());
+        }
+        if self.envs.is_none() {
+            self.envs = Some(VectorizedEnv::new(
+                self.config.num_envs,
+                &self.config,
+                self.epoch_num,
+                &mut self.rng,
+            ));
+        }
+
+        self.stats_recorder.record_new_epoch();
+        let game = loop {
+            let envs = self
+                .envs
+                .as_mut()
+                .expect("Environments should've been created");
+            if let Some(game) = envs.next_finished_game() {
+                break game;
+            }
+            if self.pending_training_steps > 0 {
+                model = self.pending_training_step(model);
+            } else {
+                envs.step(
+                    &model.valid(),
+                    &self.device,
+                    &self.critic,
+                    &self.config,
+                    &mut self.rng,
+                );
+            }
+        };
+        self.store_played_game(game, &model);
+
+        (model, self.epoch_stats())
+    }
+
+    // Seeded runs draw the randomness of every epoch from the seed and the epoch number, so that a
+    // resumed run plays the same games as an uninterrupted one
+    fn reseed_for_epoch(&mut self) {
+        if let Some(seed) = self.config.seed {
+            let epoch_num = self.epoch_num as u64;
+            self.rng = StdRng::seed_from_u64(seed ^ epoch_num.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+        }
+    }
+
+    // The training step stats are only read back from the device here, once per epoch
+    fn epoch_stats(&mut self) -> R::Stats {
+        if let Some(step_stats) = self.training_step_sums.take() {
+            self.stats_recorder.record_training_steps(&step_stats);
+        }
+        self.stats_recorder.stats()
+    }
+
+    fn is_actor_training_step_allowed(&self) -> bool {
+        if self.replay_buffer.size() < self.config.batch_size {
+            return false;
+        }
+        let ratio = self.config.actor_replay_ratio as f64;
+        ratio == 0.0 || (self.training_steps as f64) < self.earned_training_steps as f64 * ratio
+    }
+
+    // Actor learners may run ahead of the steps earned so far, which uses up the next ones
+    fn pending_training_step(&mut self, model: M) -> M {
+        let model = self.training_step(model);
+        self.pending_training_steps = self.pending_training_steps.saturating_sub(1);
+        self.training_steps += 1;
+        if self
+            .training_steps
+            .is_multiple_of(self.config.actor_sync_frequency)
+            && let Some(actors) = &self.actors
+        {
+            actors.sync_model(&model.valid());
+        }
+        model
+    }
+
+    fn store_played_game(&mut self, game: PlayedGame<S>, model: &M) {
+        self.epoch_num += 1;
+        self.stats_recorder.record_game_seed(game.game_seed);
+        self.stats_recorder.record_epsilon(game.epsilon);
+        self.episode_recorder.record_initial_state(&game.initial_state);
+
+        let previous_frame_num = self.frame_num;
+        let epoch_frames = game.steps.len();
+        let mut state = game.initial_state;
+        for step in game.steps {
+            let reward = total_reward(&step.reward_terms);
+            self.episode_recorder
+                .record_step(&step.action, reward, &step.next_state);
+            self.stats_recorder.record_reward(reward);
+            self.stats_recorder.record_reward_terms(&step.reward_terms);
+            self.replay_buffer
+                .store(state, step.action, reward, step.next_state.clone());
+            state = step.next_state;
+        }
+        self.frame_num += epoch_frames;
+        self.stats_recorder
+            .record_greedy_actions(game.num_greedy_actions, game.num_illegal_greedy_actions);
+
+        // Catches up on the training steps and target network syncs that the new frames would've
+        // triggered if the game had been played in run_epoch()
+        let crossed = |frequency: usize| self.frame_num / frequency - previous_frame_num / frequency;
+        let new_training_steps = crossed(self.config.training_frequency);
+        let is_sync_due = crossed(self.config.network_sync_frequency) > 0;
+        if self.replay_buffer.size() >= self.config.batch_size {
+            self.pending_training_steps += new_training_steps;
+            self.earned_training_steps += new_training_steps;
+        }
+        if is_sync_due {
+            self.target_network = Some(model.clone());
+        }
+
+        self.stats_recorder
+            .record_final_state(&state, epoch_frames as u32);
+        self.episode_recorder.record_final_state(&state);
+        self.stats_recorder
+            .record_replay_buffer_size(self.replay_buffer.size());
     }
 
     fn training_step(&mut self, model: M) -> M {
@@ -153,7 +416,11 @@ where
             panic!("Target network should've been set by run_epoch()")
         };
 
-        let batch = self.replay_buffer.sample(self.config.batch_size);
+        let batch = self.replay_buffer.sample(
+            self.config.batch_size,
+            self.priority_beta(),
+            &mut self.rng,
+        );
         let output = model.forward(batch.states);
         let qvalues: Tensor<B, 1> = output
             .gather(1, batch.actions.unsqueeze_dim(1))
@@ -171,59 +438,63 @@ where
             .gather(1, next_actions)
             .squeeze_dim(1);
 
-        let target_qvalues = batch.rewards
-            + (1.0 - batch.is_terminal) * self.config.discount_factor * target_qvalues;
+        let bootstrap_discount = self.config.discount_factor.powi(self.config.n_steps as i32);
+        let target_qvalues =
+            batch.rewards + (1.0 - batch.is_terminal) * bootstrap_discount * target_qvalues;
         let target_qvalues = Tensor::from_inner(target_qvalues).detach();
 
-        let loss = huber_loss.forward(qvalues, target_qvalues, Auto);
+        let td_errors = target_qvalues.clone().inner() - qvalues.clone().inner();
+        if self.replay_buffer.is_prioritized() {
+            let td_errors = td_errors.clone().into_data().to_vec::<f32>().unwrap();
+            self.replay_buffer
+                .update_priorities(&batch.indices, &td_errors);
+        }
+
+        let predicted_qvalues = qvalues.clone().inner();
+        let weights = Tensor::from_inner(batch.weights);
+        let loss = huber_loss.forward_no_reduction(qvalues, target_qvalues);
+        let loss = (loss * weights).mean();
+        let loss_value = loss.clone().inner();
         let grads = loss.backward();
         let grads = GradientsParams::from_grads(grads, &model);
 
+        self.training_step_sums.add(
+            loss_value,
+            predicted_qvalues.clone().mean(),
+            predicted_qvalues.max(),
+            td_errors.abs().mean(),
+            gradient_norm(&model, &grads, &self.device),
+        );
+
         let model = self
             .optimizer
             .step(self.config.learning_rate as f64, model, grads);
         model
     }
 
-    fn pick_action(&self, state: &S, model: &M, epsilon: f64) -> S::Action {
-        let mut rng = rng();
-        if (0.0..=1.0).sample_single(&mut rng).unwrap() <= epsilon {
-            self.pick_random_action(state, &mut rng)
+    // Annealed towards 1, where the importance sampling weights fully correct the sampling bias
+    fn priority_beta(&self) -> f32 {
+        let progress = self.frame_num as f32 / self.config.priority_beta_frames as f32;
+        let beta = self.config.priority_beta;
+        beta + (1.0 - beta) * progress.min(1.0)
+    }
+
+    fn pick_action(&mut self, state: &S, model: &M, epsilon: f64) -> S::Action {
+        if (0.0..=1.0).sample_single(&mut self.rng).unwrap() <= epsilon {
+            self.pick_random_action(state)
         } else {
             self.pick_best_action(state, model)
         }
     }
 
-    fn pick_random_action(&self, state: &S, rng: &mut ThreadRng) -> S::Action {
-        state.possible_actions().choose(rng).unwrap().clone()
-    }
-
-    fn pick_best_action(&self, state: &S, model: &M) -> S::Action {
-        let features = state.as_features();
-        let num_features = features.len();
-        let data = TensorData::new(features.into(), [1, num_features]);
-        let input = Tensor::<B::InnerBackend, 2>::from_data(data, &self.device);
-        let output = model.valid().forward(input);
-        let output: Vec<f32> = output.into_data().into_vec().unwrap();
-
-        let best_action = state
-            .possible_actions()
-            .into_iter()
-            .map(|action| {
-                let index = action.index();
-                (action, output[index])
-            })
-            .max_by(|lhs, rhs| {
-                if lhs.1 > rhs.1 {
-                    Ordering::Greater
-                } else if lhs.1 < rhs.1 {
-                    Ordering::Less
-                } else {
-                    Ordering::Equal
-                }
-            })
-            .unwrap();
-
-        best_action.0
+    fn pick_random_action(&mut self, state: &S) -> S::Action {
+        state.possible_actions().choose(&mut self.rng).unwrap().clone()
+    }
+
+    fn pick_best_action(&mut self, state: &S, model: &M) -> S::Action {
+        let (action, is_illegal) = policy::best_legal_action(state, &model.valid(), &self.device);
+        self.stats_recorder
+            .record_greedy_actions(1, is_illegal as u32);
+        action
     }
 }
@@ -1,4 +1,5 @@
 use crate::dqn::replay_buffer::StateTransition;
+use crate::dqn::state::StateType;
 use burn::prelude::TensorData;
 use burn::tensor::backend::AutodiffBackend;
 use burn::{
@@ -13,13 +14,19 @@ pub struct TrainingBatch<B: AutodiffBackend> {
     pub rewards: Tensor<B::InnerBackend, 1, Float>,
     pub next_states: Tensor<B::InnerBackend, 2, Float>,
     pub is_terminal: Tensor<B::InnerBackend, 1, Float>,
+    pub weights: Tensor<B::InnerBackend, 1, Float>,
+    pub indices: Vec<usize>,
 }
 
-impl<B: AutodiffBackend> From<Vec<&StateTransition>> for TrainingBatch<B> {
-    fn from(records: Vec<&StateTransition>) -> Self {
+impl<B: AutodiffBackend> TrainingBatch<B> {
+    pub fn new<S: StateType>(
+        records: Vec<&StateTransition<S>>,
+        indices: Vec<usize>,
+        weights: Vec<f32>,
+    ) -> Self {
         let batch_size = records.len();
-        let state_size = records[0].state.len();
-        let actions_size = records[0].invalid_actions_mask.len();
+        let state_size = S::NUM_FEATURES;
+        let actions_size = S::NUM_ACTIONS;
 
         let mut states: Vec<f32> = Vec::with_capacity(batch_size * state_size);
         let mut actions: Vec<i32> = Vec::with_capacity(batch_size);
@@ -29,12 +36,13 @@ impl<B: AutodiffBackend> From<Vec<&StateTransition>> for TrainingBatch<B> {
         let mut is_terminal: Vec<f32> = Vec::with_capacity(batch_size);
 
         for record in records {
-            states.extend_from_slice(record.state.as_slice());
+            S::extend_with_features(&record.state, &mut states);
             actions.push(record.action as i32);
-            invalid_actions_mask.extend_from_slice(record.invalid_actions_mask.as_slice());
+            invalid_actions_mask
+                .extend((0..actions_size).map(|index| record.is_action_invalid(index)));
             rewards.push(record.reward);
-            next_states.extend_from_slice(record.next_state.as_slice());
-            is_terminal.push(record.is_terminal);
+            S::extend_with_features(&record.next_state, &mut next_states);
+            is_terminal.push(if record.is_terminal { 1.0 } else { 0.0 });
         }
 
         let states = TensorData::new(states, [batch_size, state_size]);
@@ -44,6 +52,7 @@ impl<B: AutodiffBackend> From<Vec<&StateTransition>> for TrainingBatch<B> {
         let rewards = TensorData::new(rewards, [batch_size]);
         let next_states = TensorData::new(next_states, [batch_size, state_size]);
         let is_terminal = TensorData::new(is_terminal, [batch_size]);
+        let weights = TensorData::new(weights, [batch_size]);
         let device = B::Device::default();
 
         TrainingBatch {
@@ -53,6 +62,8 @@ impl<B: AutodiffBackend> From<Vec<&StateTransition>> for TrainingBatch<B> {
             rewards: Tensor::from_data(rewards, &device),
             next_states: Tensor::from_data(next_states, &device),
             is_terminal: Tensor::from_data(is_terminal, &device),
+            weights: Tensor::from_data(weights, &device),
+            indices,
         }
     }
 }
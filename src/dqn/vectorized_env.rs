@@ -0,0 +1,191 @@
+use std::collections::VecDeque;
+use std::mem;
+
+use burn::{prelude::Backend, tensor::Device};
+use rand::{Rng, distr::uniform::SampleRange, rngs::StdRng, seq::IndexedRandom};
+
+use crate::dqn::{
+    critic::CriticType, hyperparameters::Hyperparameters, model::Model, played_game::PlayedGame,
+    policy, state::StateType,
+};
+
+// Plays several games side by side, picking the greedy moves of all of them with one forward
+// pass. Finished games are queued for the trainer and replaced with new ones right away.
+pub(crate) struct VectorizedEnv<S: StateType> {
+    games: Vec<PlayedGame<S>>,
+    finished_games: VecDeque<PlayedGame<S>>,
+    started_games: usize,
+}
+
+impl<S: StateType> VectorizedEnv<S> {
+    // Game numbers continue from `started_games`, so that epsilon keeps decaying after resuming
+    pub(crate) fn new(
+        num_envs: usize,
+        config: &Hyperparameters,
+        started_games: usize,
+        rng: &mut StdRng,
+    ) -> Self {
+        let mut env = VectorizedEnv {
+            games: Vec::with_capacity(num_envs),
+            finished_games: VecDeque::new(),
+            started_games,
+        };
+        for _ in 0..num_envs {
+            let game = env.new_game(config, rng);
+            env.games.push(game);
+        }
+        env
+    }
+
+    pub(crate) fn next_finished_game(&mut self) -> Option<PlayedGame<S>> {
+        self.finished_games.pop_front()
+    }
+
+    // Makes one move in every game
+    pub(crate) fn step<B, M, C>(
+        &mut self,
+        model: &M,
+        device: &Device<B>,
+        critic: &C,
+        config: &Hyperparameters,
+        rng: &mut StdRng,
+    ) where
+        B: Backend,
+        M: Model<B>,
+        C: CriticType<State = S>,
+    {
+        let is_random: Vec<bool> = self
+            .games
+            .iter()
+            .map(|game| (0.0..=1.0).sample_single(&mut *rng).unwrap() <= game.epsilon)
+            .collect();
+        let greedy_states: Vec<&S> = self
+            .games
+            .iter()
+            .zip(&is_random)
+            .filter(|(_, is_random)| !**is_random)
+            .map(|(game, _)| game.current_state())
+            .collect();
+        let mut greedy_actions =
+            policy::best_legal_actions(&greedy_states, model, device).into_iter();
+
+        for (index, is_random) in is_random.into_iter().enumerate() {
+            let game = &mut self.games[index];
+            let state = game.current_state();
+            let (action, is_illegal) = if is_random {
+                let action = state.possible_actions().choose(&mut *rng).unwrap().clone();
+                (action, None)
+            } else {
+                let (action, is_illegal) = greedy_actions.next().unwrap();
+                (action, Some(is_illegal))
+            };
+            let next_state = state.advance(&action);
+            let reward_terms = critic.reward_terms(state, &action, &next_state);
+            if let Some(is_illegal) = is_illegal {
+                game.record_greedy_action(is_illegal);
+            }
+            game.record_step(action, reward_terms, next_state);
+
+            if game.current_state().is_terminal() {
+                let new_game = self.new_game(config, rng);
+                let finished_game = mem::replace(&mut self.games[index], new_game);
+                self.finished_games.push_back(finished_game);
+            }
+        }
+    }
+
+    fn new_game(&mut self, config: &Hyperparameters, rng: &mut StdRng) -> PlayedGame<S> {
+        self.started_games += 1;
+        PlayedGame::new(rng.random(), config.epsilon(self.started_games))
+    }
+}
+
+#[cfg(all(test, feature = "ndarray"))]
+mod tests {
+    use super::*;
+    use crate::dqn::model::tests::FixedOrderModel;
+    use crate::dqn::state::ActionType;
+    use crate::game::{board::Board, game_rng::RealGameRng};
+    use crate::training::training_critic::TrainingCritic;
+    use burn::backend::NdArray;
+    use rand::SeedableRng;
+
+    type TestState = Board<RealGameRng>;
+
+    // Prefers up, then down, left and right
+    fn test_model() -> FixedOrderModel<NdArray> {
+        FixedOrderModel::new([3.0, 2.0, 1.0, 0.0])
+    }
+
+    fn step_until_a_game_finishes(
+        env: &mut VectorizedEnv<TestState>,
+        config: &Hyperparameters,
+        rng: &mut StdRng,
+    ) -> PlayedGame<TestState> {
+        let critic = TrainingCritic::new(config);
+        for _ in 0..100_000 {
+            if let Some(game) = env.next_finished_game() {
+                return game;
+            }
+            env.step(&test_model(), &Default::default(), &critic, config, rng);
+        }
+        panic!("No game finished");
+    }
+
+    #[test]
+    fn finished_games_are_queued_and_replaced() {
+        let config = Hyperparameters::new();
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut env = VectorizedEnv::<TestState>::new(3, &config, 10, &mut rng);
+        assert_eq!(env.started_games, 13);
+        let epsilons: Vec<f64> = env.games.iter().map(|game| game.epsilon).collect();
+        assert_eq!(
+            epsilons,
+            vec![config.epsilon(11), config.epsilon(12), config.epsilon(13)]
+        );
+
+        let finished_game = step_until_a_game_finishes(&mut env, &config, &mut rng);
+
+        assert!(finished_game.current_state().is_terminal());
+        assert_eq!(env.games.len(), 3);
+        assert_eq!(env.started_games, 14);
+        let new_game = env.games.iter().find(|game| game.steps.is_empty()).unwrap();
+        assert_eq!(new_game.epsilon, config.epsilon(14));
+    }
+
+    #[test]
+    fn greedy_actions_go_to_their_own_boards() {
+        // New games only play greedy moves, the first ones alternate with always random ones
+        let config = Hyperparameters::new()
+            .with_initial_epsilon(0.0)
+            .with_min_epsilon(0.0);
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut env = VectorizedEnv::<TestState>::new(6, &config, 0, &mut rng);
+        for (index, game) in env.games.iter_mut().enumerate() {
+            game.epsilon = (index % 2) as f64;
+        }
+
+        let mut games = vec![step_until_a_game_finishes(&mut env, &config, &mut rng)];
+        games.extend(env.games);
+
+        for game in games.iter().filter(|game| game.epsilon == 0.0) {
+            assert_eq!(game.num_greedy_actions as usize, game.steps.len());
+            let mut state = &game.initial_state;
+            for step in &game.steps {
+                let preferred_action = state
+                    .possible_actions()
+                    .into_iter()
+                    .min_by_key(|action| action.index())
+                    .unwrap();
+                assert_eq!(step.action, preferred_action);
+                state = &step.next_state;
+            }
+        }
+        assert!(
+            games
+                .iter()
+                .filter(|game| game.epsilon == 1.0)
+                .all(|game| game.num_greedy_actions == 0)
+        );
+    }
+}
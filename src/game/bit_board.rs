@@ -0,0 +1,400 @@
+use std::sync::LazyLock;
+
+use crate::game::board::{Direction, NUM_COLUMNS, NUM_ROWS, NUM_TILES, NewTile, Tile};
+use crate::game::game_rng::{GameRng, RealGameRng};
+
+pub(crate) const ROW_BITS: usize = 16;
+pub(crate) const TILE_BITS: usize = 4;
+pub(crate) const TILE_MASK: u64 = 0xf;
+// Largest exponent 4 bits hold, i.e. the 32768 tile
+pub(crate) const MAX_EXPONENT: u8 = 15;
+
+static MOVE_TABLES: LazyLock<MoveTables> = LazyLock::new(MoveTables::new);
+
+// Same game as Board, but the grid is packed into a u64 holding the exponent of every tile in
+// 4 bits (row-major, first tile in the lowest bits, 0 for empty tiles). Rows are moved with
+// precomputed tables, so tiles are limited to 32768, and unlike on Board two of them don't merge.
+#[derive(Clone, Debug)]
+pub(crate) struct BitBoard<Rng: GameRng> {
+    pub(crate) score: u32,
+    cells: u64,
+    rng: Rng,
+    last_new_tile: Option<NewTile>,
+}
+
+impl BitBoard<RealGameRng> {
+    pub fn new() -> BitBoard<RealGameRng> {
+        BitBoard::new_with_rng(RealGameRng::new())
+    }
+
+    pub fn new_with_seed(seed: u64) -> BitBoard<RealGameRng> {
+        BitBoard::new_with_rng(RealGameRng::new_with_seed(seed))
+    }
+}
+
+impl<Rng: GameRng> BitBoard<Rng> {
+    pub fn new_with_rng(rng: Rng) -> BitBoard<Rng> {
+        let mut board = BitBoard {
+            score: 0,
+            cells: 0,
+            rng,
+            last_new_tile: None,
+        };
+        board.place_random_tile();
+        board.place_random_tile();
+        board
+    }
+
+    pub fn new_with_tiles(tiles: [Tile; NUM_TILES], rng: Rng) -> BitBoard<Rng> {
+        BitBoard::new_with_tiles_and_score(tiles, 0, rng)
+    }
+
+    pub fn new_with_tiles_and_score(
+        tiles: [Tile; NUM_TILES],
+        score: u32,
+        rng: Rng,
+    ) -> BitBoard<Rng> {
+        let mut board = BitBoard {
+            score,
+            cells: 0,
+            rng,
+            last_new_tile: None,
+        };
+        for (index, tile) in tiles.into_iter().enumerate() {
+            board.set_tile(index, tile);
+        }
+        board
+    }
+
+    fn place_random_tile(&mut self) {
+        loop {
+            let index = self.rng.new_tile_position();
+            if self.exponent_at(index) == 0 {
+                let value = self.rng.new_tile_value();
+                self.set_tile(index, Tile::Value(value));
+                self.last_new_tile = Some(NewTile {
+                    position: index,
+                    value,
+                });
+                break;
+            }
+        }
+    }
+
+    pub fn move_in_direction(&mut self, direction: Direction) {
+        if self.slide(direction) {
+            self.place_random_tile();
+        }
+    }
+
+    // Used to replay recorded games. On failure the board may be left partially updated.
+    pub(crate) fn move_with_new_tile(&mut self, direction: Direction, new_tile: NewTile) -> bool {
+        if !self.slide(direction)
+            || new_tile.position >= NUM_TILES
+            || self.exponent_at(new_tile.position) != 0
+        {
+            return false;
+        }
+
+        self.set_tile(new_tile.position, Tile::Value(new_tile.value));
+        self.last_new_tile = Some(new_tile);
+        true
+    }
+
+    pub fn move_right(&mut self) {
+        self.move_in_direction(Direction::Right);
+    }
+
+    pub fn move_left(&mut self) {
+        self.move_in_direction(Direction::Left);
+    }
+
+    pub fn move_down(&mut self) {
+        self.move_in_direction(Direction::Down);
+    }
+
+    pub fn move_up(&mut self) {
+        self.move_in_direction(Direction::Up);
+    }
+
+    fn slide(&mut self, direction: Direction) -> bool {
+        let (cells, score) = MOVE_TABLES.moved(self.cells, direction);
+        if cells == self.cells {
+            return false;
+        }
+
+        self.cells = cells;
+        self.score += score;
+        true
+    }
+
+    pub fn is_over(&self) -> bool {
+        is_game_over(self.cells)
+    }
+
+    pub fn value_at(&self, row: usize, column: usize) -> Option<u32> {
+        if !(0..NUM_ROWS).contains(&row) || !(0..NUM_COLUMNS).contains(&column) {
+            return None;
+        }
+
+        match self.exponent_at(row * NUM_COLUMNS + column) {
+            0 => None,
+            exponent => Some(1 << exponent),
+        }
+    }
+
+    pub fn can_move_up(&self) -> bool {
+        self.can_move(Direction::Up)
+    }
+
+    pub fn can_move_down(&self) -> bool {
+        self.can_move(Direction::Down)
+    }
+
+    pub fn can_move_left(&self) -> bool {
+        self.can_move(Direction::Left)
+    }
+
+    pub fn can_move_right(&self) -> bool {
+        self.can_move(Direction::Right)
+    }
+
+    fn can_move(&self, direction: Direction) -> bool {
+        MOVE_TABLES.moved(self.cells, direction).0 != self.cells
+    }
+
+    pub(crate) fn tiles(&self) -> [Tile; NUM_TILES] {
+        unpacked_tiles(self.cells)
+    }
+
+    pub(crate) fn cells(&self) -> u64 {
+        self.cells
+    }
+
+    pub(crate) fn last_new_tile(&self) -> Option<NewTile> {
+        self.last_new_tile
+    }
+
+    pub(crate) fn max_tile_value(&self) -> u32 {
+        match (0..NUM_TILES).map(|index| self.exponent_at(index)).max() {
+            Some(exponent) if exponent > 0 => 1 << exponent,
+            _ => 0,
+        }
+    }
+
+    fn exponent_at(&self, index: usize) -> u8 {
+        exponent_at(self.cells, index)
+    }
+
+    fn set_tile(&mut self, index: usize, tile: Tile) {
+        let exponent = match tile {
+            Tile::Empty => 0,
+            Tile::Value(value) => (value.ilog2() as u8).min(MAX_EXPONENT),
+        };
+        let shift = TILE_BITS * index;
+        self.cells = (self.cells & !(TILE_MASK << shift)) | ((exponent as u64) << shift);
+    }
+}
+
+impl<R: GameRng> PartialEq for BitBoard<R> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cells == other.cells && self.score == other.score
+    }
+}
+
+#[derive(Clone, Copy)]
+struct RowMove {
+    row: u16,
+    score: u32,
+}
+
+// Result of moving every possible row left and right
+struct MoveTables {
+    left: Vec<RowMove>,
+    right: Vec<RowMove>,
+}
+
+impl MoveTables {
+    fn new() -> Self {
+        let num_rows = 1 << ROW_BITS;
+        let left: Vec<RowMove> = (0..num_rows)
+            .map(|row| slide_row_left(row as u16))
+            .collect();
+        let right = (0..num_rows)
+            .map(|row| {
+                let moved = left[reverse_row(row as u16) as usize];
+                RowMove {
+                    row: reverse_row(moved.row),
+                    score: moved.score,
+                }
+            })
+            .collect();
+
+        MoveTables { left, right }
+    }
+
+    // Columns are moved as rows of the transposed grid
+    fn moved(&self, cells: u64, direction: Direction) -> (u64, u32) {
+        match direction {
+            Direction::Left => move_rows(cells, &self.left),
+            Direction::Right => move_rows(cells, &self.right),
+            Direction::Up => {
+                let (cells, score) = move_rows(transpose(cells), &self.left);
+                (transpose(cells), score)
+            }
+            Direction::Down => {
+                let (cells, score) = move_rows(transpose(cells), &self.right);
+                (transpose(cells), score)
+            }
+        }
+    }
+}
+
+// Exponent of the tile at `index` in packed cells, 0 for empty tiles
+pub(crate) fn exponent_at(cells: u64, index: usize) -> u8 {
+    ((cells >> (TILE_BITS * index)) & TILE_MASK) as u8
+}
+
+pub(crate) fn unpacked_tiles(cells: u64) -> [Tile; NUM_TILES] {
+    std::array::from_fn(|index| match exponent_at(cells, index) {
+        0 => Tile::Empty,
+        exponent => Tile::Value(1 << exponent),
+    })
+}
+
+pub(crate) fn is_game_over(cells: u64) -> bool {
+    Direction::ALL
+        .into_iter()
+        .all(|direction| MOVE_TABLES.moved(cells, direction).0 == cells)
+}
+
+// Moves packed cells without placing a new tile, for searches that place tiles themselves.
+// Returns the moved cells and the score gained.
+pub(crate) fn moved_cells(cells: u64, direction: Direction) -> (u64, u32) {
+    MOVE_TABLES.moved(cells, direction)
+}
+
+fn move_rows(cells: u64, table: &[RowMove]) -> (u64, u32) {
+    let mut moved_cells = 0;
+    let mut score = 0;
+    for row in 0..NUM_ROWS {
+        let shift = ROW_BITS * row;
+        let row_move = table[((cells >> shift) & 0xffff) as usize];
+        moved_cells |= (row_move.row as u64) << shift;
+        score += row_move.score;
+    }
+    (moved_cells, score)
+}
+
+fn slide_row_left(row: u16) -> RowMove {
+    let mut tiles = [0u8; NUM_COLUMNS];
+    let mut num_tiles = 0;
+    let mut can_merge = false;
+    let mut score = 0;
+
+    for column in 0..NUM_COLUMNS {
+        let exponent = ((row >> (TILE_BITS * column)) & TILE_MASK as u16) as u8;
+        if exponent == 0 {
+            continue;
+        }
+        if can_merge && tiles[num_tiles - 1] == exponent && exponent < MAX_EXPONENT {
+            tiles[num_tiles - 1] = exponent + 1;
+            score += 1 << (exponent + 1);
+            can_merge = false;
+        } else {
+            tiles[num_tiles] = exponent;
+            num_tiles += 1;
+            can_merge = true;
+        }
+    }
+
+    let row = tiles.iter().enumerate().fold(0, |row, (column, exponent)| {
+        row | (*exponent as u16) << (TILE_BITS * column)
+    });
+    RowMove { row, score }
+}
+
+fn reverse_row(row: u16) -> u16 {
+    (row >> 12) | ((row >> 4) & 0x00f0) | ((row << 4) & 0x0f00) | (row << 12)
+}
+
+pub(crate) fn transpose(cells: u64) -> u64 {
+    let a1 = cells & 0xf0f0_0f0f_f0f0_0f0f;
+    let a2 = cells & 0x0000_f0f0_0000_f0f0;
+    let a3 = cells & 0x0f0f_0000_0f0f_0000;
+    let a = a1 | (a2 << 12) | (a3 >> 12);
+    let b1 = a & 0xff00_ff00_00ff_00ff;
+    let b2 = a & 0x00ff_00ff_0000_0000;
+    let b3 = a & 0x0000_0000_ff00_ff00;
+    b1 | (b2 >> 24) | (b3 << 24)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::board::{Board, board_tests};
+
+    use super::*;
+
+    board_tests!(BitBoard);
+
+    #[test]
+    fn transposing_twice_restores_the_grid() {
+        let cells = 0x0123_4567_89ab_cdef;
+        assert_eq!(transpose(transpose(cells)), cells);
+        assert_eq!(transpose(cells) & 0xf0, 0xb0);
+    }
+
+    #[test]
+    fn packed_cells_unpack_to_their_tiles() {
+        // 2 2 . . / . 4 . . / ... / . . . 2048
+        let cells = 0xb000_0000_0020_0011;
+
+        let tiles = unpacked_tiles(cells);
+
+        assert_eq!(tiles[0], Tile::Value(2));
+        assert_eq!(tiles[2], Tile::Empty);
+        assert_eq!(tiles[5], Tile::Value(4));
+        assert_eq!(tiles[15], Tile::Value(2048));
+        assert_eq!(exponent_at(cells, 15), 11);
+    }
+
+    #[test]
+    fn cells_without_moves_are_over() {
+        // 2 4 2 4 / 4 2 4 2 / ...
+        assert!(is_game_over(0x1212_2121_1212_2121));
+        assert!(!is_game_over(0x1212_2121_1212_2111));
+    }
+
+    #[test]
+    fn largest_tiles_dont_merge() {
+        // 32768 32768 . . in the first row
+        let cells = 0x00ff;
+
+        assert_eq!(moved_cells(cells, Direction::Left), (cells, 0));
+        assert_eq!(moved_cells(cells, Direction::Right), (0xff00, 0));
+    }
+
+    #[test]
+    fn plays_the_same_games_as_board() {
+        let directions = [
+            Direction::Up,
+            Direction::Left,
+            Direction::Left,
+            Direction::Down,
+            Direction::Right,
+        ];
+
+        for seed in 0..20 {
+            let mut board = Board::new_with_seed(seed);
+            let mut bit_board = BitBoard::new_with_seed(seed);
+            for direction in directions.iter().cycle().take(500) {
+                board.move_in_direction(*direction);
+                bit_board.move_in_direction(*direction);
+
+                assert_eq!(board.tiles(), bit_board.tiles(), "seed {}", seed);
+                assert_eq!(board.score, bit_board.score, "seed {}", seed);
+                assert_eq!(board.is_over(), bit_board.is_over(), "seed {}", seed);
+            }
+        }
+    }
+}
@@ -1,6 +1,3 @@
-use std::ptr::eq;
-use rand::prelude::*;
-
 use crate::game::game_rng::{GameRng, RealGameRng};
 
 #[derive(Copy, Clone, PartialEq, Debug)]
@@ -17,6 +14,22 @@ pub(crate) enum Direction {
     Right,
 }
 
+impl Direction {
+    // Ordered like the action indices of the models
+    pub(crate) const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+    ];
+}
+
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub(crate) struct NewTile {
+    pub position: usize,
+    pub value: u32,
+}
+
 pub(crate) const NUM_ROWS: usize = 4;
 pub(crate) const NUM_COLUMNS: usize = 4;
 pub(crate) const NUM_TILES: usize = NUM_ROWS * NUM_COLUMNS;
@@ -26,32 +39,48 @@ pub(crate) struct Board<Rng: GameRng> {
     pub(crate) score: u32,
     tiles: [Tile; NUM_TILES],
     rng: Rng,
+    last_new_tile: Option<NewTile>,
 }
 
 impl Board<RealGameRng> {
     pub fn new() -> Board<RealGameRng> {
+        Board::new_with_rng(RealGameRng::new())
+    }
+
+    pub fn new_with_seed(seed: u64) -> Board<RealGameRng> {
+        Board::new_with_rng(RealGameRng::new_with_seed(seed))
+    }
+}
+
+impl<Rng: GameRng> Board<Rng> {
+    pub fn new_with_rng(rng: Rng) -> Board<Rng> {
         let mut board = Board {
             score: 0,
             tiles: [Tile::Empty; NUM_TILES],
-            rng: RealGameRng::new(),
+            rng,
+            last_new_tile: None,
         };
         board.place_random_tile();
         board.place_random_tile();
         board
     }
-}
 
-impl<Rng: GameRng> Board<Rng> {
     pub fn new_with_tiles(tiles: [Tile; NUM_TILES], rng: Rng) -> Board<Rng> {
         Board {
             score: 0,
             tiles: tiles,
             rng: rng,
+            last_new_tile: None,
         }
     }
 
     pub fn new_with_tiles_and_score(tiles: [Tile; NUM_TILES], score: u32, rng: Rng) -> Board<Rng> {
-        Board { score, tiles, rng }
+        Board {
+            score,
+            tiles,
+            rng,
+            last_new_tile: None,
+        }
     }
 
     fn place_random_tile(&mut self) {
@@ -59,15 +88,52 @@ impl<Rng: GameRng> Board<Rng> {
         loop {
             let index = self.rng.new_tile_position();
             if self.tiles[index] == Tile::Empty {
-                self.tiles[index] = Tile::Value(self.rng.new_tile_value());
+                let value = self.rng.new_tile_value();
+                self.tiles[index] = Tile::Value(value);
+                self.last_new_tile = Some(NewTile {
+                    position: index,
+                    value,
+                });
                 break;
             }
         }
     }
 
+    pub fn move_in_direction(&mut self, direction: Direction) {
+        match direction {
+            Direction::Up => self.move_up(),
+            Direction::Down => self.move_down(),
+            Direction::Left => self.move_left(),
+            Direction::Right => self.move_right(),
+        }
+    }
+
+    // Used to replay recorded games. On failure the board may be left partially updated.
+    pub(crate) fn move_with_new_tile(&mut self, direction: Direction, new_tile: NewTile) -> bool {
+        let has_moved = match direction {
+            Direction::Up => self.slide_up(),
+            Direction::Down => self.slide_down(),
+            Direction::Left => self.slide_left(),
+            Direction::Right => self.slide_right(),
+        };
+        if !has_moved || self.tiles.get(new_tile.position) != Some(&Tile::Empty) {
+            return false;
+        }
+
+        self.tiles[new_tile.position] = Tile::Value(new_tile.value);
+        self.last_new_tile = Some(new_tile);
+        true
+    }
+
     pub fn move_right(&mut self) {
+        if self.slide_right() {
+            self.place_random_tile();
+        }
+    }
+
+    fn slide_right(&mut self) -> bool {
         if !self.can_move_right() {
-            return;
+            return false;
         }
         let mut merged_tiles = [false; NUM_TILES];
         for column in (0..NUM_COLUMNS - 1).rev() {
@@ -75,12 +141,18 @@ impl<Rng: GameRng> Board<Rng> {
                 self.slide_tile(row, column, Direction::Right, &mut merged_tiles);
             }
         }
-        self.place_random_tile();
+        true
     }
 
     pub fn move_left(&mut self) {
+        if self.slide_left() {
+            self.place_random_tile();
+        }
+    }
+
+    fn slide_left(&mut self) -> bool {
         if !self.can_move_left() {
-            return;
+            return false;
         }
         let mut merged_tiles = [false; NUM_TILES];
         for column in 1..NUM_COLUMNS {
@@ -88,12 +160,18 @@ impl<Rng: GameRng> Board<Rng> {
                 self.slide_tile(row, column, Direction::Left, &mut merged_tiles);
             }
         }
-        self.place_random_tile();
+        true
     }
 
     pub fn move_down(&mut self) {
+        if self.slide_down() {
+            self.place_random_tile();
+        }
+    }
+
+    fn slide_down(&mut self) -> bool {
         if !self.can_move_down() {
-            return;
+            return false;
         }
         let mut merged_tiles = [false; NUM_TILES];
         for row in (0..NUM_ROWS - 1).rev() {
@@ -101,12 +179,18 @@ impl<Rng: GameRng> Board<Rng> {
                 self.slide_tile(row, column, Direction::Down, &mut merged_tiles);
             }
         }
-        self.place_random_tile();
+        true
     }
 
     pub fn move_up(&mut self) {
+        if self.slide_up() {
+            self.place_random_tile();
+        }
+    }
+
+    fn slide_up(&mut self) -> bool {
         if !self.can_move_up() {
-            return;
+            return false;
         }
         let mut merged_tiles = [false; NUM_TILES];
         for row in 1..NUM_ROWS {
@@ -114,7 +198,7 @@ impl<Rng: GameRng> Board<Rng> {
                 self.slide_tile(row, column, Direction::Up, &mut merged_tiles);
             }
         }
-        self.place_random_tile();
+        true
     }
 
     fn slide_tile(
@@ -279,6 +363,14 @@ impl<Rng: GameRng> Board<Rng> {
         }
     }
 
+    pub(crate) fn tiles(&self) -> [Tile; NUM_TILES] {
+        self.tiles
+    }
+
+    pub(crate) fn last_new_tile(&self) -> Option<NewTile> {
+        self.last_new_tile
+    }
+
     pub(crate) fn max_tile_value(&self) -> u32 {
         self.tiles.iter()
             .filter_map(|item| {
@@ -297,10 +389,6 @@ impl<R: GameRng> PartialEq for Board<R> {
         self.tiles == other.tiles &&
             self.score == other.score
     }
-
-    fn ne(&self, other: &Self) -> bool {
-        !eq(self, other)
-    }
 }
 
 fn index(row: usize, column: usize) -> usize {
@@ -318,623 +406,658 @@ impl Direction {
     }
 }
 
+// The tests are shared with BitBoard, which has to behave exactly the same
 #[cfg(test)]
-mod tests {
-    use std::iter::Zip;
-
-    use crate::game::game_rng::FakeGameRng;
-
-    use super::Tile::{Empty, Value};
-    use super::*;
+macro_rules! board_tests {
+    ($board:ident) => {
+        use crate::game::board::Tile::{Empty, Value};
+        use crate::game::board::{Direction, NUM_TILES, Tile};
+        use crate::game::game_rng::FakeGameRng;
+
+        struct MergeConfiguration {
+            initial_tiles: [Tile; NUM_TILES],
+            direction: Direction,
+            expected_tiles: [Tile; NUM_TILES],
+            expected_score: u32,
+            new_tile_index: usize,
+        }
 
-    struct MergeConfiguration {
-        initial_tiles: [Tile; NUM_TILES],
-        direction: Direction,
-        expected_tiles: [Tile; NUM_TILES],
-        expected_score: u32,
-        new_tile_index: usize,
-    }
+        #[test]
+        fn new_board_with_two_tiles() {
+            let board = $board::new();
 
-    #[test]
-    fn new_board_with_two_tiles() {
-        let board = Board::new();
-
-        assert_eq!(board.score, 0);
-        assert_eq!(
-            board
-                .tiles
-                .iter()
-                .filter(|tile| {
-                    match tile {
-                        Tile::Empty => false,
-                        Tile::Value(value) if *value == 2 || *value == 4 => true,
-                        Tile::Value(value) => {
-                            panic!("{} is not a correct tile value", value)
+            assert_eq!(board.score, 0);
+            assert_eq!(
+                board
+                    .tiles()
+                    .iter()
+                    .filter(|tile| {
+                        match tile {
+                            Tile::Empty => false,
+                            Tile::Value(value) if *value == 2 || *value == 4 => true,
+                            Tile::Value(value) => {
+                                panic!("{} is not a correct tile value", value)
+                            }
                         }
-                    }
-                })
-                .count(),
-            2
-        );
-    }
+                    })
+                    .count(),
+                2
+            );
+        }
 
-    #[test]
-    fn move_right() {
-        let new_tile_value = 2;
-        let new_tile_index = 0;
-
-        #[rustfmt::skip]
-        let mut board = Board::new_with_tiles([
-            Value(2), Empty,    Empty,    Empty,
-            Value(2), Value(4), Empty,    Empty,
-            Value(2), Value(4), Value(8), Empty,
-            Value(2), Value(4), Value(8), Value(16),
-        ], FakeGameRng::new(new_tile_value, new_tile_index));
-
-        #[rustfmt::skip]
-        let expected_result: [Tile; _] = [
-            Value(2), Empty,    Empty,    Value(2), 
-            Empty,    Empty,    Value(2), Value(4), 
-            Empty,    Value(2), Value(4), Value(8),
-            Value(2), Value(4), Value(8), Value(16),
-        ];
-
-        board.move_right();
-
-        assert_eq!(board.tiles, expected_result);
-    }
+        #[test]
+        fn move_right() {
+            let new_tile_value = 2;
+            let new_tile_index = 0;
+
+            #[rustfmt::skip]
+            let mut board = $board::new_with_tiles([
+                Value(2), Empty,    Empty,    Empty,
+                Value(2), Value(4), Empty,    Empty,
+                Value(2), Value(4), Value(8), Empty,
+                Value(2), Value(4), Value(8), Value(16),
+            ], FakeGameRng::new(new_tile_value, new_tile_index));
+
+            #[rustfmt::skip]
+            let expected_result: [Tile; _] = [
+                Value(2), Empty,    Empty,    Value(2), 
+                Empty,    Empty,    Value(2), Value(4), 
+                Empty,    Value(2), Value(4), Value(8),
+                Value(2), Value(4), Value(8), Value(16),
+            ];
+
+            board.move_right();
+
+            assert_eq!(board.tiles(), expected_result);
+        }
 
-    #[test]
-    fn move_left() {
-        let new_tile_value = 2;
-        let new_tile_index = 3;
-
-        #[rustfmt::skip]
-        let mut board = Board::new_with_tiles([
-            Empty,    Empty,    Empty,    Value(2), 
-            Empty,    Empty,    Value(2), Value(4), 
-            Empty,    Value(2), Value(4), Value(8),
-            Value(2), Value(4), Value(8), Value(16),
-        ], FakeGameRng::new(new_tile_value, new_tile_index));
-
-        #[rustfmt::skip]
-        let expected_result: [Tile; _] = [
-            Value(2), Empty,    Empty,    Value(2),
-            Value(2), Value(4), Empty,    Empty,
-            Value(2), Value(4), Value(8), Empty,
-            Value(2), Value(4), Value(8), Value(16),
-        ];
-
-        board.move_left();
-
-        assert_eq!(board.tiles, expected_result);
-    }
+        #[test]
+        fn move_left() {
+            let new_tile_value = 2;
+            let new_tile_index = 3;
+
+            #[rustfmt::skip]
+            let mut board = $board::new_with_tiles([
+                Empty,    Empty,    Empty,    Value(2), 
+                Empty,    Empty,    Value(2), Value(4), 
+                Empty,    Value(2), Value(4), Value(8),
+                Value(2), Value(4), Value(8), Value(16),
+            ], FakeGameRng::new(new_tile_value, new_tile_index));
+
+            #[rustfmt::skip]
+            let expected_result: [Tile; _] = [
+                Value(2), Empty,    Empty,    Value(2),
+                Value(2), Value(4), Empty,    Empty,
+                Value(2), Value(4), Value(8), Empty,
+                Value(2), Value(4), Value(8), Value(16),
+            ];
+
+            board.move_left();
+
+            assert_eq!(board.tiles(), expected_result);
+        }
 
-    #[test]
-    fn move_down() {
-        let new_tile_value = 2;
-        let new_tile_index = 0;
-
-        #[rustfmt::skip]
-        let mut board = Board::new_with_tiles([
-            Value(2), Value(4), Value(8), Value(16),
-            Empty,    Value(2), Value(4), Value(8),
-            Empty,    Empty,    Value(2), Value(4), 
-            Empty,    Empty,    Empty,    Value(2), 
-        ], FakeGameRng::new(new_tile_value, new_tile_index));
-
-        #[rustfmt::skip]
-        let expected_result: [Tile; _] = [
-            Value(2), Empty,    Empty,     Value(16),
-            Empty,    Empty,    Value(8),  Value(8),
-            Empty,    Value(4), Value(4),  Value(4), 
-            Value(2), Value(2), Value(2),  Value(2), 
-        ];
-
-        board.move_down();
-
-        assert_eq!(board.tiles, expected_result);
-    }
+        #[test]
+        fn move_down() {
+            let new_tile_value = 2;
+            let new_tile_index = 0;
+
+            #[rustfmt::skip]
+            let mut board = $board::new_with_tiles([
+                Value(2), Value(4), Value(8), Value(16),
+                Empty,    Value(2), Value(4), Value(8),
+                Empty,    Empty,    Value(2), Value(4), 
+                Empty,    Empty,    Empty,    Value(2), 
+            ], FakeGameRng::new(new_tile_value, new_tile_index));
+
+            #[rustfmt::skip]
+            let expected_result: [Tile; _] = [
+                Value(2), Empty,    Empty,     Value(16),
+                Empty,    Empty,    Value(8),  Value(8),
+                Empty,    Value(4), Value(4),  Value(4), 
+                Value(2), Value(2), Value(2),  Value(2), 
+            ];
+
+            board.move_down();
+
+            assert_eq!(board.tiles(), expected_result);
+        }
 
-    #[test]
-    fn move_up() {
-        let new_tile_value = 2;
-        let new_tile_index = 12;
-
-        #[rustfmt::skip]
-        let mut board = Board::new_with_tiles([
-            Empty,    Empty,    Empty,     Value(16),
-            Empty,    Empty,    Value(8),  Value(8),
-            Empty,    Value(4), Value(4),  Value(4), 
-            Value(2), Value(2), Value(2),  Value(2), 
-        ], FakeGameRng::new(new_tile_value, new_tile_index));
-
-        #[rustfmt::skip]
-        let expected_result: [Tile; _] = [
-            Value(2), Value(4), Value(8), Value(16),
-            Empty,    Value(2), Value(4), Value(8),
-            Empty,    Empty,    Value(2), Value(4), 
-            Value(2), Empty,    Empty,    Value(2), 
-        ];
-
-        board.move_up();
-
-        assert_eq!(board.tiles, expected_result);
-    }
+        #[test]
+        fn move_up() {
+            let new_tile_value = 2;
+            let new_tile_index = 12;
+
+            #[rustfmt::skip]
+            let mut board = $board::new_with_tiles([
+                Empty,    Empty,    Empty,     Value(16),
+                Empty,    Empty,    Value(8),  Value(8),
+                Empty,    Value(4), Value(4),  Value(4), 
+                Value(2), Value(2), Value(2),  Value(2), 
+            ], FakeGameRng::new(new_tile_value, new_tile_index));
+
+            #[rustfmt::skip]
+            let expected_result: [Tile; _] = [
+                Value(2), Value(4), Value(8), Value(16),
+                Empty,    Value(2), Value(4), Value(8),
+                Empty,    Empty,    Value(2), Value(4), 
+                Value(2), Empty,    Empty,    Value(2), 
+            ];
+
+            board.move_up();
+
+            assert_eq!(board.tiles(), expected_result);
+        }
 
-    #[test]
-    fn merging_tiles() {
-        #[rustfmt::skip]
-        let configurations: [MergeConfiguration; _] = [
-            // Merge up
-            MergeConfiguration {
-                initial_tiles: [
-                    Value(2), Value(4), Value(8), Value(16),
-                    Value(2), Value(4), Value(8), Value(16),
-                    Empty,    Empty,    Empty,    Empty,
-                    Empty,    Empty,    Empty,    Empty,
-                ],
-                direction: Direction::Up,
-                expected_tiles: [
-                    Value(4), Value(8), Value(16), Value(32),
-                    Empty,    Empty,    Empty,    Empty,
-                    Empty,    Empty,    Empty,    Empty,
-                    Empty,    Empty,    Empty,    Value(2),
-                ],
-                expected_score: 4 + 8 + 16 + 32,
-                new_tile_index: 15,
-            },
-            // Merge down
-            MergeConfiguration {
-                initial_tiles: [
-                    Empty,    Empty,    Empty,    Empty,
-                    Empty,    Empty,    Empty,    Empty,
-                    Value(2), Value(4), Value(8), Value(16),
-                    Value(2), Value(4), Value(8), Value(16),
-                ],
-                direction: Direction::Down,
-                expected_tiles: [
-                    Empty,    Empty,    Empty,    Value(2),
-                    Empty,    Empty,    Empty,    Empty,
-                    Empty,    Empty,    Empty,    Empty,
-                    Value(4), Value(8), Value(16), Value(32),
-                ],
-                expected_score: 4 + 8 + 16 + 32,
-                new_tile_index: 3,
-            },
-            // Merge left
-            MergeConfiguration {
-                initial_tiles: [
-                    Value(2),  Value(2),  Empty, Empty,
-                    Value(4),  Value(4),  Empty, Empty,
-                    Value(8),  Value(8),  Empty, Empty,
-                    Value(16), Value(16), Empty, Empty,
-                ],
-                direction: Direction::Left,
-                expected_tiles: [
-                    Value(4),  Empty, Empty, Empty,
-                    Value(8),  Empty, Empty, Empty,
-                    Value(16), Empty, Empty, Empty,
-                    Value(32), Empty, Empty, Value(2),
-                ],
-                expected_score: 4 + 8 + 16 + 32,
-                new_tile_index: 15,
-            },
-            // Merge right
-            MergeConfiguration {
-                initial_tiles: [
-                    Empty, Empty, Value(2),  Value(2),  
-                    Empty, Empty, Value(4),  Value(4),  
-                    Empty, Empty, Value(8),  Value(8),  
-                    Empty, Empty, Value(16), Value(16), 
-                ],
-                direction: Direction::Right,
-                expected_tiles: [
-                    Empty,    Empty, Empty, Value(4),  
-                    Empty,    Empty, Empty, Value(8),  
-                    Empty,    Empty, Empty, Value(16), 
-                    Value(2), Empty, Empty, Value(32), 
-                ],
-                expected_score: 4 + 8 + 16 + 32,
-                new_tile_index: 12,
-            },
-        ];
-
-        for config in configurations {
-            let mut board = Board::new_with_tiles(
-                config.initial_tiles,
-                FakeGameRng::new(2, config.new_tile_index),
-            );
-            match config.direction {
-                Direction::Up => board.move_up(),
-                Direction::Down => board.move_down(),
-                Direction::Left => board.move_left(),
-                Direction::Right => board.move_right(),
+        #[test]
+        fn merging_tiles() {
+            #[rustfmt::skip]
+            let configurations: [MergeConfiguration; _] = [
+                // Merge up
+                MergeConfiguration {
+                    initial_tiles: [
+                        Value(2), Value(4), Value(8), Value(16),
+                        Value(2), Value(4), Value(8), Value(16),
+                        Empty,    Empty,    Empty,    Empty,
+                        Empty,    Empty,    Empty,    Empty,
+                    ],
+                    direction: Direction::Up,
+                    expected_tiles: [
+                        Value(4), Value(8), Value(16), Value(32),
+                        Empty,    Empty,    Empty,    Empty,
+                        Empty,    Empty,    Empty,    Empty,
+                        Empty,    Empty,    Empty,    Value(2),
+                    ],
+                    expected_score: 4 + 8 + 16 + 32,
+                    new_tile_index: 15,
+                },
+                // Merge down
+                MergeConfiguration {
+                    initial_tiles: [
+                        Empty,    Empty,    Empty,    Empty,
+                        Empty,    Empty,    Empty,    Empty,
+                        Value(2), Value(4), Value(8), Value(16),
+                        Value(2), Value(4), Value(8), Value(16),
+                    ],
+                    direction: Direction::Down,
+                    expected_tiles: [
+                        Empty,    Empty,    Empty,    Value(2),
+                        Empty,    Empty,    Empty,    Empty,
+                        Empty,    Empty,    Empty,    Empty,
+                        Value(4), Value(8), Value(16), Value(32),
+                    ],
+                    expected_score: 4 + 8 + 16 + 32,
+                    new_tile_index: 3,
+                },
+                // Merge left
+                MergeConfiguration {
+                    initial_tiles: [
+                        Value(2),  Value(2),  Empty, Empty,
+                        Value(4),  Value(4),  Empty, Empty,
+                        Value(8),  Value(8),  Empty, Empty,
+                        Value(16), Value(16), Empty, Empty,
+                    ],
+                    direction: Direction::Left,
+                    expected_tiles: [
+                        Value(4),  Empty, Empty, Empty,
+                        Value(8),  Empty, Empty, Empty,
+                        Value(16), Empty, Empty, Empty,
+                        Value(32), Empty, Empty, Value(2),
+                    ],
+                    expected_score: 4 + 8 + 16 + 32,
+                    new_tile_index: 15,
+                },
+                // Merge right
+                MergeConfiguration {
+                    initial_tiles: [
+                        Empty, Empty, Value(2),  Value(2),  
+                        Empty, Empty, Value(4),  Value(4),  
+                        Empty, Empty, Value(8),  Value(8),  
+                        Empty, Empty, Value(16), Value(16), 
+                    ],
+                    direction: Direction::Right,
+                    expected_tiles: [
+                        Empty,    Empty, Empty, Value(4),  
+                        Empty,    Empty, Empty, Value(8),  
+                        Empty,    Empty, Empty, Value(16), 
+                        Value(2), Empty, Empty, Value(32), 
+                    ],
+                    expected_score: 4 + 8 + 16 + 32,
+                    new_tile_index: 12,
+                },
+            ];
+
+            for config in configurations {
+                let mut board = $board::new_with_tiles(
+                    config.initial_tiles,
+                    FakeGameRng::new(2, config.new_tile_index),
+                );
+                match config.direction {
+                    Direction::Up => board.move_up(),
+                    Direction::Down => board.move_down(),
+                    Direction::Left => board.move_left(),
+                    Direction::Right => board.move_right(),
+                }
+                assert_eq!(
+                    board.tiles(), config.expected_tiles,
+                    "Merge failed in direction {:?}",
+                    config.direction
+                );
+                assert_eq!(
+                    board.score, config.expected_score,
+                    "Incorrect score after the merge in direction {:?}",
+                    config.direction
+                );
             }
-            assert_eq!(
-                board.tiles, config.expected_tiles,
-                "Merge failed in direction {:?}",
-                config.direction
-            );
-            assert_eq!(
-                board.score, config.expected_score,
-                "Incorrect score after the merge in direction {:?}",
-                config.direction
-            );
         }
-    }
 
-    #[test]
-    fn merging_3_tiles() {
-        #[rustfmt::skip]
-        let configurations: [MergeConfiguration; _] = [
-            // Merge up
-            MergeConfiguration {
-                initial_tiles: [
-                    Value(2), Value(4), Value(8), Value(16),
-                    Value(2), Value(4), Value(8), Value(16),
-                    Value(2), Value(4), Value(8), Value(16),
-                    Empty,    Empty,    Empty,    Empty,
-                ],
-                direction: Direction::Up,
-                expected_tiles: [
-                    Value(4), Value(8), Value(16), Value(32),
-                    Value(2), Value(4), Value(8),  Value(16),
-                    Empty,    Empty,    Empty,     Empty,
-                    Empty,    Empty,    Empty,     Value(2),
-                ],
-                expected_score: 4 + 8 + 16 + 32,
-                new_tile_index: 15,
-            },
-            // Merge down
-            MergeConfiguration {
-                initial_tiles: [
-                    Empty,    Empty,    Empty,    Empty,
-                    Value(2), Value(4), Value(8), Value(16),
-                    Value(2), Value(4), Value(8), Value(16),
-                    Value(2), Value(4), Value(8), Value(16),
-                ],
-                direction: Direction::Down,
-                expected_tiles: [
-                    Empty,    Empty,    Empty,     Value(2),
-                    Empty,    Empty,    Empty,     Empty,
-                    Value(2), Value(4), Value(8),  Value(16),
-                    Value(4), Value(8), Value(16), Value(32),
-                ],
-                expected_score: 4 + 8 + 16 + 32,
-                new_tile_index: 3,
-            },
-            // Merge left
-            MergeConfiguration {
-                initial_tiles: [
-                    Value(2),  Value(2),  Value(2),  Empty,
-                    Value(4),  Value(4),  Value(4),  Empty,
-                    Value(8),  Value(8),  Value(8),  Empty,
-                    Value(16), Value(16), Value(16), Empty,
-                ],
-                direction: Direction::Left,
-                expected_tiles: [
-                    Value(4),  Value(2),  Empty, Empty,
-                    Value(8),  Value(4),  Empty, Empty,
-                    Value(16), Value(8),  Empty, Empty,
-                    Value(32), Value(16), Empty, Value(2),
-                ],
-                expected_score: 4 + 8 + 16 + 32,
-                new_tile_index: 15,
-            },
-            // Merge right
-            MergeConfiguration {
-                initial_tiles: [
-                    Empty, Value(2),  Value(2),  Value(2),  
-                    Empty, Value(4),  Value(4),  Value(4),  
-                    Empty, Value(8),  Value(8),  Value(8),  
-                    Empty, Value(16), Value(16), Value(16), 
-                ],
-                direction: Direction::Right,
-                expected_tiles: [
-                    Empty,    Empty, Value(2),  Value(4),  
-                    Empty,    Empty, Value(4),  Value(8),  
-                    Empty,    Empty, Value(8),  Value(16), 
-                    Value(2), Empty, Value(16), Value(32), 
-                ],
-                expected_score: 4 + 8 + 16 + 32,
-                new_tile_index: 12,
-            },
-        ];
-
-        for config in configurations {
-            let mut board = Board::new_with_tiles(
-                config.initial_tiles,
-                FakeGameRng::new(2, config.new_tile_index),
-            );
-            match config.direction {
-                Direction::Up => board.move_up(),
-                Direction::Down => board.move_down(),
-                Direction::Left => board.move_left(),
-                Direction::Right => board.move_right(),
+        #[test]
+        fn merging_3_tiles() {
+            #[rustfmt::skip]
+            let configurations: [MergeConfiguration; _] = [
+                // Merge up
+                MergeConfiguration {
+                    initial_tiles: [
+                        Value(2), Value(4), Value(8), Value(16),
+                        Value(2), Value(4), Value(8), Value(16),
+                        Value(2), Value(4), Value(8), Value(16),
+                        Empty,    Empty,    Empty,    Empty,
+                    ],
+                    direction: Direction::Up,
+                    expected_tiles: [
+                        Value(4), Value(8), Value(16), Value(32),
+                        Value(2), Value(4), Value(8),  Value(16),
+                        Empty,    Empty,    Empty,     Empty,
+                        Empty,    Empty,    Empty,     Value(2),
+                    ],
+                    expected_score: 4 + 8 + 16 + 32,
+                    new_tile_index: 15,
+                },
+                // Merge down
+                MergeConfiguration {
+                    initial_tiles: [
+                        Empty,    Empty,    Empty,    Empty,
+                        Value(2), Value(4), Value(8), Value(16),
+                        Value(2), Value(4), Value(8), Value(16),
+                        Value(2), Value(4), Value(8), Value(16),
+                    ],
+                    direction: Direction::Down,
+                    expected_tiles: [
+                        Empty,    Empty,    Empty,     Value(2),
+                        Empty,    Empty,    Empty,     Empty,
+                        Value(2), Value(4), Value(8),  Value(16),
+                        Value(4), Value(8), Value(16), Value(32),
+                    ],
+                    expected_score: 4 + 8 + 16 + 32,
+                    new_tile_index: 3,
+                },
+                // Merge left
+                MergeConfiguration {
+                    initial_tiles: [
+                        Value(2),  Value(2),  Value(2),  Empty,
+                        Value(4),  Value(4),  Value(4),  Empty,
+                        Value(8),  Value(8),  Value(8),  Empty,
+                        Value(16), Value(16), Value(16), Empty,
+                    ],
+                    direction: Direction::Left,
+                    expected_tiles: [
+                        Value(4),  Value(2),  Empty, Empty,
+                        Value(8),  Value(4),  Empty, Empty,
+                        Value(16), Value(8),  Empty, Empty,
+                        Value(32), Value(16), Empty, Value(2),
+                    ],
+                    expected_score: 4 + 8 + 16 + 32,
+                    new_tile_index: 15,
+                },
+                // Merge right
+                MergeConfiguration {
+                    initial_tiles: [
+                        Empty, Value(2),  Value(2),  Value(2),  
+                        Empty, Value(4),  Value(4),  Value(4),  
+                        Empty, Value(8),  Value(8),  Value(8),  
+                        Empty, Value(16), Value(16), Value(16), 
+                    ],
+                    direction: Direction::Right,
+                    expected_tiles: [
+                        Empty,    Empty, Value(2),  Value(4),  
+                        Empty,    Empty, Value(4),  Value(8),  
+                        Empty,    Empty, Value(8),  Value(16), 
+                        Value(2), Empty, Value(16), Value(32), 
+                    ],
+                    expected_score: 4 + 8 + 16 + 32,
+                    new_tile_index: 12,
+                },
+            ];
+
+            for config in configurations {
+                let mut board = $board::new_with_tiles(
+                    config.initial_tiles,
+                    FakeGameRng::new(2, config.new_tile_index),
+                );
+                match config.direction {
+                    Direction::Up => board.move_up(),
+                    Direction::Down => board.move_down(),
+                    Direction::Left => board.move_left(),
+                    Direction::Right => board.move_right(),
+                }
+                assert_eq!(
+                    board.tiles(), config.expected_tiles,
+                    "Merge failed in direction {:?}",
+                    config.direction
+                );
+                assert_eq!(
+                    board.score, config.expected_score,
+                    "Incorrect score after the merge in direction {:?}",
+                    config.direction
+                );
             }
-            assert_eq!(
-                board.tiles, config.expected_tiles,
-                "Merge failed in direction {:?}",
-                config.direction
-            );
-            assert_eq!(
-                board.score, config.expected_score,
-                "Incorrect score after the merge in direction {:?}",
-                config.direction
-            );
         }
-    }
 
-    #[test]
-    fn merging_4_tiles() {
-        #[rustfmt::skip]
-        let configurations: [MergeConfiguration; _] = [
-            // Merge up
-            MergeConfiguration {
-                initial_tiles: [
-                    Value(2), Value(4), Value(8), Value(16),
-                    Value(2), Value(4), Value(8), Value(16),
-                    Value(2), Value(4), Value(8), Value(16),
-                    Value(2), Value(4), Value(8), Value(16),
-                ],
-                direction: Direction::Up,
-                expected_tiles: [
-                    Value(4), Value(8), Value(16), Value(32),
-                    Value(4), Value(8), Value(16), Value(32),
-                    Empty,    Empty,    Empty,     Empty,
-                    Empty,    Empty,    Empty,     Value(2),
-                ],
-                expected_score: 2 * (4 + 8 + 16 + 32),
-                new_tile_index: 15,
-            },
-            // Merge down
-            MergeConfiguration {
-                initial_tiles: [
-                    Value(2), Value(4), Value(8), Value(16),
-                    Value(2), Value(4), Value(8), Value(16),
-                    Value(2), Value(4), Value(8), Value(16),
-                    Value(2), Value(4), Value(8), Value(16),
-                ],
-                direction: Direction::Down,
-                expected_tiles: [
-                    Empty,    Empty,    Empty,     Value(2),
-                    Empty,    Empty,    Empty,     Empty,
-                    Value(4), Value(8), Value(16), Value(32),
-                    Value(4), Value(8), Value(16), Value(32),
-                ],
-                expected_score: 2 * (4 + 8 + 16 + 32),
-                new_tile_index: 3,
-            },
-            // Merge left
-            MergeConfiguration {
-                initial_tiles: [
-                    Value(2),  Value(2),  Value(2),  Value(2),  
-                    Value(4),  Value(4),  Value(4),  Value(4),  
-                    Value(8),  Value(8),  Value(8),  Value(8),  
-                    Value(16), Value(16), Value(16), Value(16), 
-                ],
-                direction: Direction::Left,
-                expected_tiles: [
-                    Value(4),  Value(4),  Empty, Empty,
-                    Value(8),  Value(8),  Empty, Empty,
-                    Value(16), Value(16), Empty, Empty,
-                    Value(32), Value(32), Empty, Value(2),
-                ],
-                expected_score: 2 * (4 + 8 + 16 + 32),
-                new_tile_index: 15,
-            },
-            // Merge right
-            MergeConfiguration {
-                initial_tiles: [
-                    Value(2),  Value(2),  Value(2),  Value(2),  
-                    Value(4),  Value(4),  Value(4),  Value(4),  
-                    Value(8),  Value(8),  Value(8),  Value(8),  
-                    Value(16), Value(16), Value(16), Value(16), 
-                ],
-                direction: Direction::Right,
-                expected_tiles: [
-                    Empty,    Empty, Value(4),  Value(4),  
-                    Empty,    Empty, Value(8),  Value(8),  
-                    Empty,    Empty, Value(16), Value(16), 
-                    Value(2), Empty, Value(32), Value(32), 
-                ],
-                expected_score: 2 * (4 + 8 + 16 + 32),
-                new_tile_index: 12,
-            },
-        ];
-
-        for config in configurations {
-            let mut board = Board::new_with_tiles(
-                config.initial_tiles,
-                FakeGameRng::new(2, config.new_tile_index),
-            );
-            match config.direction {
-                Direction::Up => board.move_up(),
-                Direction::Down => board.move_down(),
-                Direction::Left => board.move_left(),
-                Direction::Right => board.move_right(),
+        #[test]
+        fn merging_4_tiles() {
+            #[rustfmt::skip]
+            let configurations: [MergeConfiguration; _] = [
+                // Merge up
+                MergeConfiguration {
+                    initial_tiles: [
+                        Value(2), Value(4), Value(8), Value(16),
+                        Value(2), Value(4), Value(8), Value(16),
+                        Value(2), Value(4), Value(8), Value(16),
+                        Value(2), Value(4), Value(8), Value(16),
+                    ],
+                    direction: Direction::Up,
+                    expected_tiles: [
+                        Value(4), Value(8), Value(16), Value(32),
+                        Value(4), Value(8), Value(16), Value(32),
+                        Empty,    Empty,    Empty,     Empty,
+                        Empty,    Empty,    Empty,     Value(2),
+                    ],
+                    expected_score: 2 * (4 + 8 + 16 + 32),
+                    new_tile_index: 15,
+                },
+                // Merge down
+                MergeConfiguration {
+                    initial_tiles: [
+                        Value(2), Value(4), Value(8), Value(16),
+                        Value(2), Value(4), Value(8), Value(16),
+                        Value(2), Value(4), Value(8), Value(16),
+                        Value(2), Value(4), Value(8), Value(16),
+                    ],
+                    direction: Direction::Down,
+                    expected_tiles: [
+                        Empty,    Empty,    Empty,     Value(2),
+                        Empty,    Empty,    Empty,     Empty,
+                        Value(4), Value(8), Value(16), Value(32),
+                        Value(4), Value(8), Value(16), Value(32),
+                    ],
+                    expected_score: 2 * (4 + 8 + 16 + 32),
+                    new_tile_index: 3,
+                },
+                // Merge left
+                MergeConfiguration {
+                    initial_tiles: [
+                        Value(2),  Value(2),  Value(2),  Value(2),  
+                        Value(4),  Value(4),  Value(4),  Value(4),  
+                        Value(8),  Value(8),  Value(8),  Value(8),  
+                        Value(16), Value(16), Value(16), Value(16), 
+                    ],
+                    direction: Direction::Left,
+                    expected_tiles: [
+                        Value(4),  Value(4),  Empty, Empty,
+                        Value(8),  Value(8),  Empty, Empty,
+                        Value(16), Value(16), Empty, Empty,
+                        Value(32), Value(32), Empty, Value(2),
+                    ],
+                    expected_score: 2 * (4 + 8 + 16 + 32),
+                    new_tile_index: 15,
+                },
+                // Merge right
+                MergeConfiguration {
+                    initial_tiles: [
+                        Value(2),  Value(2),  Value(2),  Value(2),  
+                        Value(4),  Value(4),  Value(4),  Value(4),  
+                        Value(8),  Value(8),  Value(8),  Value(8),  
+                        Value(16), Value(16), Value(16), Value(16), 
+                    ],
+                    direction: Direction::Right,
+                    expected_tiles: [
+                        Empty,    Empty, Value(4),  Value(4),  
+                        Empty,    Empty, Value(8),  Value(8),  
+                        Empty,    Empty, Value(16), Value(16), 
+                        Value(2), Empty, Value(32), Value(32), 
+                    ],
+                    expected_score: 2 * (4 + 8 + 16 + 32),
+                    new_tile_index: 12,
+                },
+            ];
+
+            for config in configurations {
+                let mut board = $board::new_with_tiles(
+                    config.initial_tiles,
+                    FakeGameRng::new(2, config.new_tile_index),
+                );
+                match config.direction {
+                    Direction::Up => board.move_up(),
+                    Direction::Down => board.move_down(),
+                    Direction::Left => board.move_left(),
+                    Direction::Right => board.move_right(),
+                }
+                assert_eq!(
+                    board.tiles(), config.expected_tiles,
+                    "Merge failed in direction {:?}",
+                    config.direction
+                );
+                assert_eq!(
+                    board.score, config.expected_score,
+                    "Incorrect score after the merge in direction {:?}",
+                    config.direction
+                );
             }
-            assert_eq!(
-                board.tiles, config.expected_tiles,
-                "Merge failed in direction {:?}",
-                config.direction
-            );
-            assert_eq!(
-                board.score, config.expected_score,
-                "Incorrect score after the merge in direction {:?}",
-                config.direction
-            );
         }
-    }
 
-    #[test]
-    fn new_board_is_not_over() {
-        let board = Board::new();
-        assert_eq!(board.is_over(), false);
-    }
+        #[test]
+        fn boards_with_the_same_seed_play_identically() {
+            let mut board = $board::new_with_seed(2048);
+            let mut same_seed_board = $board::new_with_seed(2048);
+            assert_eq!(board, same_seed_board);
+
+            let directions = [
+                Direction::Up,
+                Direction::Left,
+                Direction::Down,
+                Direction::Right,
+            ];
+            for direction in directions.iter().cycle().take(40) {
+                for board in [&mut board, &mut same_seed_board] {
+                    match direction {
+                        Direction::Up => board.move_up(),
+                        Direction::Down => board.move_down(),
+                        Direction::Left => board.move_left(),
+                        Direction::Right => board.move_right(),
+                    }
+                }
+                assert_eq!(board, same_seed_board);
+            }
+        }
 
-    #[test]
-    fn full_board_with_no_possible_move_is_over() {
-        #[rustfmt::skip]
-        let board = Board::new_with_tiles([
-            Value(2), Value(4), Value(2), Value(4),
-            Value(4), Value(2), Value(4), Value(2),
-            Value(2), Value(4), Value(2), Value(4),
-            Value(4), Value(2), Value(4), Value(2),
-        ], FakeGameRng::new(2, 2));
-        assert_eq!(board.is_over(), true);
-    }
+        #[test]
+        fn new_board_is_not_over() {
+            let board = $board::new();
+            assert_eq!(board.is_over(), false);
+        }
 
-    #[test]
-    fn full_board_with_possible_moves_is_not_over() {
-        #[rustfmt::skip]
-        let board = Board::new_with_tiles([
-            Value(2), Value(4), Value(2), Value(4),
-            Value(2), Value(2), Value(4), Value(2),
-            Value(8), Value(4), Value(2), Value(8),
-            Value(4), Value(2), Value(4), Value(4),
-        ], FakeGameRng::new(2, 2));
-        assert_eq!(board.is_over(), false);
-    }
+        #[test]
+        fn full_board_with_no_possible_move_is_over() {
+            #[rustfmt::skip]
+            let board = $board::new_with_tiles([
+                Value(2), Value(4), Value(2), Value(4),
+                Value(4), Value(2), Value(4), Value(2),
+                Value(2), Value(4), Value(2), Value(4),
+                Value(4), Value(2), Value(4), Value(2),
+            ], FakeGameRng::new(2, 2));
+            assert_eq!(board.is_over(), true);
+        }
 
-    #[test]
-    fn can_move_up() {
-        #[rustfmt::skip]
-        let configurations: Vec<[Tile; NUM_TILES]> = vec![
-            [
-                Value(2), Empty, Empty, Empty,
-                Empty,    Empty, Empty, Empty,
-                Empty,    Empty, Empty, Empty,
-                Empty,    Empty, Empty, Empty,
-            ], [
-                Empty,    Empty, Empty, Empty,
-                Value(2), Empty, Empty, Empty,
-                Empty,    Empty, Empty, Empty,
-                Empty,    Empty, Empty, Empty,
-            ], [
-                Value(2), Empty, Empty, Empty,
-                Value(2), Empty, Empty, Empty,
-                Empty,    Empty, Empty, Empty,
-                Empty,    Empty, Empty, Empty,
-            ],
-        ];
-
-        let expected_results = vec![false, true, true];
-
-        for (index, example) in configurations.into_iter().zip(expected_results).enumerate() {
-            let board = Board::new_with_tiles(example.0, FakeGameRng::new(2, 2));
-            assert_eq!(
-                board.can_move_up(),
-                example.1,
-                "Test failed for configuration {}",
-                index + 1
-            );
+        #[test]
+        fn full_board_with_possible_moves_is_not_over() {
+            #[rustfmt::skip]
+            let board = $board::new_with_tiles([
+                Value(2), Value(4), Value(2), Value(4),
+                Value(2), Value(2), Value(4), Value(2),
+                Value(8), Value(4), Value(2), Value(8),
+                Value(4), Value(2), Value(4), Value(4),
+            ], FakeGameRng::new(2, 2));
+            assert_eq!(board.is_over(), false);
         }
-    }
 
-    #[test]
-    fn can_move_down() {
-        #[rustfmt::skip]
-        let configurations: Vec<[Tile; NUM_TILES]> = vec![
-            [
-                Empty,    Empty, Empty, Empty,
-                Empty,    Empty, Empty, Empty,
-                Empty,    Empty, Empty, Empty,
-                Value(2), Empty, Empty, Empty,
-            ], [
-                Empty,    Empty, Empty, Empty,
-                Empty,    Empty, Empty, Empty,
-                Value(2), Empty, Empty, Empty,
-                Empty,    Empty, Empty, Empty,
-            ], [
-                Empty,    Empty, Empty, Empty,
-                Empty,    Empty, Empty, Empty,
-                Value(2), Empty, Empty, Empty,
-                Value(2), Empty, Empty, Empty,
-            ],
-        ];
-
-        let expected_results = vec![false, true, true];
-
-        for (index, example) in configurations.into_iter().zip(expected_results).enumerate() {
-            let board = Board::new_with_tiles(example.0, FakeGameRng::new(2, 2));
-            assert_eq!(
-                board.can_move_down(),
-                example.1,
-                "Test failed for configuration {}",
-                index + 1
-            );
+        #[test]
+        fn can_move_up() {
+            #[rustfmt::skip]
+            let configurations: Vec<[Tile; NUM_TILES]> = vec![
+                [
+                    Value(2), Empty, Empty, Empty,
+                    Empty,    Empty, Empty, Empty,
+                    Empty,    Empty, Empty, Empty,
+                    Empty,    Empty, Empty, Empty,
+                ], [
+                    Empty,    Empty, Empty, Empty,
+                    Value(2), Empty, Empty, Empty,
+                    Empty,    Empty, Empty, Empty,
+                    Empty,    Empty, Empty, Empty,
+                ], [
+                    Value(2), Empty, Empty, Empty,
+                    Value(2), Empty, Empty, Empty,
+                    Empty,    Empty, Empty, Empty,
+                    Empty,    Empty, Empty, Empty,
+                ],
+            ];
+
+            let expected_results = vec![false, true, true];
+
+            for (index, example) in configurations.into_iter().zip(expected_results).enumerate() {
+                let board = $board::new_with_tiles(example.0, FakeGameRng::new(2, 2));
+                assert_eq!(
+                    board.can_move_up(),
+                    example.1,
+                    "Test failed for configuration {}",
+                    index + 1
+                );
+            }
         }
-    }
 
-    #[test]
-    fn can_move_left() {
-        #[rustfmt::skip]
-        let configurations: Vec<[Tile; NUM_TILES]> = vec![
-            [
-                Value(2), Empty, Empty, Empty,
-                Empty,    Empty, Empty, Empty,
-                Empty,    Empty, Empty, Empty,
-                Empty,    Empty, Empty, Empty,
-            ], [
-                Empty, Value(2), Empty, Empty,
-                Empty, Empty,    Empty, Empty,
-                Empty, Empty,    Empty, Empty,
-                Empty, Empty,    Empty, Empty,
-            ], [
-                Value(2), Value(2), Empty, Empty,
-                Empty,    Empty,    Empty, Empty,
-                Empty,    Empty,    Empty, Empty,
-                Empty,    Empty,    Empty, Empty,
-            ],
-        ];
-
-        let expected_results = vec![false, true, true];
-
-        for (index, example) in configurations.into_iter().zip(expected_results).enumerate() {
-            let board = Board::new_with_tiles(example.0, FakeGameRng::new(2, 2));
-            assert_eq!(
-                board.can_move_left(),
-                example.1,
-                "Test failed for configuration {}",
-                index + 1
-            );
+        #[test]
+        fn can_move_down() {
+            #[rustfmt::skip]
+            let configurations: Vec<[Tile; NUM_TILES]> = vec![
+                [
+                    Empty,    Empty, Empty, Empty,
+                    Empty,    Empty, Empty, Empty,
+                    Empty,    Empty, Empty, Empty,
+                    Value(2), Empty, Empty, Empty,
+                ], [
+                    Empty,    Empty, Empty, Empty,
+                    Empty,    Empty, Empty, Empty,
+                    Value(2), Empty, Empty, Empty,
+                    Empty,    Empty, Empty, Empty,
+                ], [
+                    Empty,    Empty, Empty, Empty,
+                    Empty,    Empty, Empty, Empty,
+                    Value(2), Empty, Empty, Empty,
+                    Value(2), Empty, Empty, Empty,
+                ],
+            ];
+
+            let expected_results = vec![false, true, true];
+
+            for (index, example) in configurations.into_iter().zip(expected_results).enumerate() {
+                let board = $board::new_with_tiles(example.0, FakeGameRng::new(2, 2));
+                assert_eq!(
+                    board.can_move_down(),
+                    example.1,
+                    "Test failed for configuration {}",
+                    index + 1
+                );
+            }
         }
-    }
 
-    #[test]
-    fn can_move_right() {
-        #[rustfmt::skip]
-        let configurations: Vec<[Tile; NUM_TILES]> = vec![
-            [
-                Empty, Empty, Empty, Value(2),
-                Empty, Empty, Empty, Empty,
-                Empty, Empty, Empty, Empty,
-                Empty, Empty, Empty, Empty,
-            ], [
-                Empty, Empty, Value(2), Empty,
-                Empty, Empty, Empty,    Empty,
-                Empty, Empty, Empty,    Empty,
-                Empty, Empty, Empty,    Empty,
-            ], [
-                Empty, Empty, Value(2), Value(2),
-                Empty, Empty, Empty,    Empty,
-                Empty, Empty, Empty,    Empty,
-                Empty, Empty, Empty,    Empty,
-            ],
-        ];
-
-        let expected_results = vec![false, true, true];
-
-        for (index, example) in configurations.into_iter().zip(expected_results).enumerate() {
-            let board = Board::new_with_tiles(example.0, FakeGameRng::new(2, 2));
-            assert_eq!(
-                board.can_move_right(),
-                example.1,
-                "Test failed for configuration {}",
-                index + 1
-            );
+        #[test]
+        fn can_move_left() {
+            #[rustfmt::skip]
+            let configurations: Vec<[Tile; NUM_TILES]> = vec![
+                [
+                    Value(2), Empty, Empty, Empty,
+                    Empty,    Empty, Empty, Empty,
+                    Empty,    Empty, Empty, Empty,
+                    Empty,    Empty, Empty, Empty,
+                ], [
+                    Empty, Value(2), Empty, Empty,
+                    Empty, Empty,    Empty, Empty,
+                    Empty, Empty,    Empty, Empty,
+                    Empty, Empty,    Empty, Empty,
+                ], [
+                    Value(2), Value(2), Empty, Empty,
+                    Empty,    Empty,    Empty, Empty,
+                    Empty,    Empty,    Empty, Empty,
+                    Empty,    Empty,    Empty, Empty,
+                ],
+            ];
+
+            let expected_results = vec![false, true, true];
+
+            for (index, example) in configurations.into_iter().zip(expected_results).enumerate() {
+                let board = $board::new_with_tiles(example.0, FakeGameRng::new(2, 2));
+                assert_eq!(
+                    board.can_move_left(),
+                    example.1,
+                    "Test failed for configuration {}",
+                    index + 1
+                );
+            }
         }
-    }
+
+        #[test]
+        fn can_move_right() {
+            #[rustfmt::skip]
+            let configurations: Vec<[Tile; NUM_TILES]> = vec![
+                [
+                    Empty, Empty, Empty, Value(2),
+                    Empty, Empty, Empty, Empty,
+                    Empty, Empty, Empty, Empty,
+                    Empty, Empty, Empty, Empty,
+                ], [
+                    Empty, Empty, Value(2), Empty,
+                    Empty, Empty, Empty,    Empty,
+                    Empty, Empty, Empty,    Empty,
+                    Empty, Empty, Empty,    Empty,
+                ], [
+                    Empty, Empty, Value(2), Value(2),
+                    Empty, Empty, Empty,    Empty,
+                    Empty, Empty, Empty,    Empty,
+                    Empty, Empty, Empty,    Empty,
+                ],
+            ];
+
+            let expected_results = vec![false, true, true];
+
+            for (index, example) in configurations.into_iter().zip(expected_results).enumerate() {
+                let board = $board::new_with_tiles(example.0, FakeGameRng::new(2, 2));
+                assert_eq!(
+                    board.can_move_right(),
+                    example.1,
+                    "Test failed for configuration {}",
+                    index + 1
+                );
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+pub(crate) use board_tests;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    board_tests!(Board);
 }
@@ -0,0 +1,322 @@
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::Path;
+
+use crate::game::board::{Board, Direction, NUM_TILES, NewTile, Tile};
+use crate::game::game_rng::{GameRng, RealGameRng};
+
+const MAGIC: &[u8; 4] = b"2048";
+const VERSION: u8 = 1;
+
+#[derive(Clone, PartialEq, Debug)]
+pub(crate) struct EpisodeStep {
+    pub direction: Direction,
+    pub new_tile: NewTile,
+    pub reward: f32,
+}
+
+// A single recorded game. On disk each step takes 5 bytes: the direction, new tile position and
+// new tile value packed into one byte, followed by the reward.
+#[derive(Clone, PartialEq, Debug)]
+pub(crate) struct Episode {
+    pub initial_tiles: [Tile; NUM_TILES],
+    pub steps: Vec<EpisodeStep>,
+    pub final_score: u32,
+}
+
+impl Episode {
+    pub(crate) fn new<R: GameRng>(initial_board: &Board<R>) -> Episode {
+        Episode {
+            initial_tiles: initial_board.tiles(),
+            steps: Vec::new(),
+            final_score: initial_board.score,
+        }
+    }
+
+    // Fails if the board didn't place a new tile, since the step couldn't be replayed
+    pub(crate) fn record_step<R: GameRng>(
+        &mut self,
+        direction: Direction,
+        reward: f32,
+        next_board: &Board<R>,
+    ) -> io::Result<()> {
+        let Some(new_tile) = next_board.last_new_tile() else {
+            return Err(invalid_data(format!(
+                "Step {} didn't place a new tile",
+                self.steps.len() + 1
+            )));
+        };
+        self.steps.push(EpisodeStep {
+            direction,
+            new_tile,
+            reward,
+        });
+        self.final_score = next_board.score;
+        Ok(())
+    }
+
+    pub(crate) fn boards(&self) -> io::Result<Vec<Board<RealGameRng>>> {
+        let mut board = Board::new_with_tiles(self.initial_tiles, RealGameRng::new());
+        let mut boards = Vec::with_capacity(self.steps.len() + 1);
+        boards.push(board.clone());
+
+        for (index, step) in self.steps.iter().enumerate() {
+            if !board.move_with_new_tile(step.direction, step.new_tile) {
+                return Err(invalid_data(format!(
+                    "Step {} is not a legal move",
+                    index + 1
+                )));
+            }
+            boards.push(board.clone());
+        }
+
+        if board.score != self.final_score {
+            return Err(invalid_data(format!(
+                "Replayed score {} doesn't match the recorded score {}",
+                board.score, self.final_score
+            )));
+        }
+
+        Ok(boards)
+    }
+
+    pub(crate) fn save(&self, file_path: &Path) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(file_path)?);
+        self.write_to(&mut writer)?;
+        writer.flush()
+    }
+
+    pub(crate) fn load(file_path: &Path) -> io::Result<Episode> {
+        let mut reader = BufReader::new(File::open(file_path)?);
+        let episode = Episode::read_from(&mut reader)?;
+        episode.boards()?;
+        Ok(episode)
+    }
+
+    pub(crate) fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION])?;
+        for tile in self.initial_tiles {
+            writer.write_all(&[encode_tile(tile)?])?;
+        }
+        writer.write_all(&(self.steps.len() as u32).to_le_bytes())?;
+        for step in &self.steps {
+            writer.write_all(&[encode_step(step)?])?;
+            writer.write_all(&step.reward.to_le_bytes())?;
+        }
+        writer.write_all(&self.final_score.to_le_bytes())
+    }
+
+    pub(crate) fn read_from(reader: &mut impl Read) -> io::Result<Episode> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid_data("Not an episode file".to_string()));
+        }
+        let [version] = read_bytes(reader)?;
+        if version != VERSION {
+            return Err(invalid_data(format!(
+                "Unsupported episode version {}",
+                version
+            )));
+        }
+
+        let mut initial_tiles = [Tile::Empty; NUM_TILES];
+        for tile in initial_tiles.iter_mut() {
+            let [exponent] = read_bytes(reader)?;
+            *tile = decode_tile(exponent)?;
+        }
+
+        let num_steps = u32::from_le_bytes(read_bytes(reader)?);
+        let mut steps = Vec::with_capacity(num_steps as usize);
+        for _ in 0..num_steps {
+            let [packed_step] = read_bytes(reader)?;
+            let reward = f32::from_le_bytes(read_bytes(reader)?);
+            steps.push(decode_step(packed_step, reward));
+        }
+        let final_score = u32::from_le_bytes(read_bytes(reader)?);
+
+        Ok(Episode {
+            initial_tiles,
+            steps,
+            final_score,
+        })
+    }
+}
+
+fn read_bytes<const N: usize>(reader: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut bytes = [0u8; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, message)
+}
+
+fn encode_tile(tile: Tile) -> io::Result<u8> {
+    match tile {
+        Tile::Empty => Ok(0),
+        Tile::Value(value) if value.is_power_of_two() && value > 1 => Ok(value.ilog2() as u8),
+        Tile::Value(value) => Err(invalid_data(format!("Invalid tile value {}", value))),
+    }
+}
+
+fn decode_tile(exponent: u8) -> io::Result<Tile> {
+    match exponent {
+        0 => Ok(Tile::Empty),
+        1..32 => Ok(Tile::Value(1 << exponent)),
+        _ => Err(invalid_data(format!("Invalid tile exponent {}", exponent))),
+    }
+}
+
+// Bits 0-3 hold the new tile position, bit 4 is set for a new 4 and bits 5-6 hold the direction.
+fn encode_step(step: &EpisodeStep) -> io::Result<u8> {
+    let value_bit = match step.new_tile.value {
+        2 => 0,
+        4 => 1,
+        value => return Err(invalid_data(format!("Invalid new tile value {}", value))),
+    };
+    let direction_bits = match step.direction {
+        Direction::Up => 0,
+        Direction::Down => 1,
+        Direction::Left => 2,
+        Direction::Right => 3,
+    };
+    Ok(step.new_tile.position as u8 | value_bit << 4 | direction_bits << 5)
+}
+
+fn decode_step(packed_step: u8, reward: f32) -> EpisodeStep {
+    let direction = match (packed_step >> 5) & 0b11 {
+        0 => Direction::Up,
+        1 => Direction::Down,
+        2 => Direction::Left,
+        _ => Direction::Right,
+    };
+    let value = if packed_step & 0b1_0000 == 0 { 2 } else { 4 };
+
+    EpisodeStep {
+        direction,
+        new_tile: NewTile {
+            position: (packed_step & 0b1111) as usize,
+            value,
+        },
+        reward,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::board::Tile::{Empty, Value};
+
+    fn record_episode(seed: u64, max_steps: usize) -> Episode {
+        let directions = [
+            Direction::Up,
+            Direction::Left,
+            Direction::Down,
+            Direction::Right,
+        ];
+        let mut board = Board::new_with_seed(seed);
+        let mut episode = Episode::new(&board);
+
+        for (step, direction) in directions.iter().cycle().take(max_steps).enumerate() {
+            if board.is_over() {
+                break;
+            }
+            let previous_tiles = board.tiles();
+            board.move_in_direction(*direction);
+            if board.tiles() != previous_tiles {
+                episode
+                    .record_step(*direction, step as f32, &board)
+                    .unwrap();
+            }
+        }
+        episode
+    }
+
+    #[test]
+    fn episode_survives_round_trip() {
+        let episode = record_episode(7, 200);
+        let mut bytes = Vec::new();
+        episode.write_to(&mut bytes).unwrap();
+
+        assert_eq!(
+            bytes.len(),
+            4 + 1 + NUM_TILES + 4 + 5 * episode.steps.len() + 4
+        );
+        assert_eq!(Episode::read_from(&mut bytes.as_slice()).unwrap(), episode);
+    }
+
+    #[test]
+    fn replay_rebuilds_recorded_boards() {
+        let directions = [
+            Direction::Left,
+            Direction::Up,
+            Direction::Right,
+            Direction::Down,
+        ];
+        let mut board = Board::new_with_seed(11);
+        let mut expected_boards = vec![board.clone()];
+        let mut episode = Episode::new(&board);
+        for direction in directions.iter().cycle().take(20) {
+            let previous_tiles = board.tiles();
+            board.move_in_direction(*direction);
+            if board.tiles() != previous_tiles {
+                episode.record_step(*direction, 0.0, &board).unwrap();
+                expected_boards.push(board.clone());
+            }
+        }
+
+        assert_eq!(episode.boards().unwrap(), expected_boards);
+    }
+
+    #[test]
+    fn illegal_steps_are_rejected() {
+        #[rustfmt::skip]
+        let initial_tiles = [
+            Value(2), Empty, Empty, Empty,
+            Empty,    Empty, Empty, Empty,
+            Empty,    Empty, Empty, Empty,
+            Empty,    Empty, Empty, Empty,
+        ];
+        let episode = Episode {
+            initial_tiles,
+            steps: vec![EpisodeStep {
+                direction: Direction::Up,
+                new_tile: NewTile {
+                    position: 5,
+                    value: 2,
+                },
+                reward: 0.0,
+            }],
+            final_score: 0,
+        };
+
+        assert!(episode.boards().is_err());
+    }
+
+    #[test]
+    fn corrupted_files_are_rejected() {
+        let mut bytes = Vec::new();
+        record_episode(3, 50).write_to(&mut bytes).unwrap();
+
+        assert!(Episode::read_from(&mut &bytes[..bytes.len() - 1]).is_err());
+        bytes[0] = b'x';
+        assert!(Episode::read_from(&mut bytes.as_slice()).is_err());
+    }
+
+    #[test]
+    fn steps_without_new_tile_are_rejected() {
+        let board = Board::new_with_seed(5);
+        let mut episode = Episode::new(&board);
+        let next_board = Board::new_with_tiles(board.tiles(), RealGameRng::new());
+
+        assert!(
+            episode
+                .record_step(Direction::Left, 0.0, &next_board)
+                .is_err()
+        );
+        assert!(episode.steps.is_empty());
+    }
+}
@@ -1,38 +1,49 @@
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
 use crate::game::board::NUM_TILES;
 
 pub(crate) trait GameRng: Default {
-    fn new_tile_value(&self) -> u32;
-    fn new_tile_position(&self) -> usize;
+    fn new_tile_value(&mut self) -> u32;
+    fn new_tile_position(&mut self) -> usize;
 }
 
 #[derive(Clone, Debug)]
-pub(crate) struct RealGameRng {}
+pub(crate) struct RealGameRng {
+    rng: StdRng,
+}
 
 impl RealGameRng {
     pub(crate) fn new() -> Self {
-        RealGameRng {}
+        RealGameRng {
+            rng: StdRng::from_rng(&mut rand::rng()),
+        }
+    }
+
+    pub(crate) fn new_with_seed(seed: u64) -> Self {
+        RealGameRng {
+            rng: StdRng::seed_from_u64(seed),
+        }
     }
 }
 
 impl Default for RealGameRng {
     fn default() -> Self {
-        RealGameRng {}
+        RealGameRng::new()
     }
 }
 
 impl GameRng for RealGameRng {
-    fn new_tile_value(&self) -> u32 {
-        if rand::rng().random::<f64>() <= 0.1 {
+    fn new_tile_value(&mut self) -> u32 {
+        if self.rng.random::<f64>() <= 0.1 {
             4
         } else {
             2
         }
     }
 
-    fn new_tile_position(&self) -> usize {
-        rand::rng().random_range(0..NUM_TILES)
+    fn new_tile_position(&mut self) -> usize {
+        self.rng.random_range(0..NUM_TILES)
     }
 }
 
@@ -60,11 +71,11 @@ impl Default for FakeGameRng {
 }
 
 impl GameRng for FakeGameRng {
-    fn new_tile_value(&self) -> u32 {
+    fn new_tile_value(&mut self) -> u32 {
         self.tile_value
     }
 
-    fn new_tile_position(&self) -> usize {
+    fn new_tile_position(&mut self) -> usize {
         self.tile_position
     }
 }
@@ -1,2 +1,4 @@
+pub(crate) mod bit_board;
 pub(crate) mod board;
+pub(crate) mod episode;
 pub(crate) mod game_rng;
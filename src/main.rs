@@ -1,21 +1,36 @@
 // Prevent console window in addition to Slint window in Windows release builds when, e.g., starting the app via file manager. Ignored on other platforms.
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod benchmark;
+mod cli;
 mod dqn;
 mod game;
+mod search;
 mod training;
 mod ui;
 
+use crate::cli::agent_benchmark::AgentBenchmark;
+use crate::cli::cli_options::{CliCommand, WindowOptions};
+use crate::cli::episode_replay::EpisodeReplay;
+use crate::cli::headless_training::HeadlessTraining;
+use crate::cli::model_evaluation::ModelEvaluation;
+use crate::cli::search_evaluation::SearchEvaluation;
+use crate::dqn::hyperparameters::Hyperparameters;
 use crate::training::training_thread::TrainingThread;
 use crate::training::types::TrainingAction;
-use crate::ui::training_overview::TrainingOverviewUpdate::PlotsSizesChanged;
 use crate::ui::training_overview::{
-    PlotRangeType, PlotsSettings, PlotsSizes, TrainingOverviewThread, TrainingOverviewUpdate,
+    PlotRangeType, PlotsSettings, TrainingOverviewThread, TrainingOverviewUpdate,
 };
+use crate::ui::agent_thread::{AgentAction, AgentThread};
+use crate::ui::human_game::HumanGame;
+use crate::ui::hyperparameters_settings::{hyperparameters_from_model, hyperparameters_model};
 use crate::ui::training_update_adapter::TrainingUpdateAdapter;
 use burn::backend::Autodiff;
+use burn::tensor::backend::AutodiffBackend;
 #[cfg(feature = "cuda")]
 use burn::backend::Cuda;
+#[cfg(feature = "ndarray")]
+use burn::backend::NdArray;
 #[cfg(feature = "rocm")]
 use burn::backend::Rocm;
 #[cfg(feature = "wgpu")]
@@ -23,37 +38,105 @@ use burn::backend::Wgpu;
 use num_format::{SystemLocale, ToFormattedString};
 use plotters::prelude::*;
 use rfd::FileDialog;
-use slint::{quit_event_loop, Timer, TimerMode, Weak};
+use slint::{quit_event_loop, Model, Timer, TimerMode, Weak};
+use std::cell::RefCell;
+use std::env;
 use std::error::Error;
+use std::rc::Rc;
 use std::sync::mpsc::Sender;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 slint::include_modules!();
 
+#[cfg(not(any(
+    feature = "cuda",
+    feature = "rocm",
+    feature = "wgpu",
+    feature = "ndarray"
+)))]
+compile_error!(
+    "No backend selected, enable one of the cuda, rocm, wgpu or ndarray features, e.g. `cargo run --no-default-features --features ndarray`"
+);
+#[cfg(any(
+    all(feature = "cuda", any(feature = "rocm", feature = "wgpu", feature = "ndarray")),
+    all(feature = "rocm", any(feature = "wgpu", feature = "ndarray")),
+    all(feature = "wgpu", feature = "ndarray")
+))]
+compile_error!(
+    "Only one backend feature can be enabled, cuda is on by default so other backends need `--no-default-features`, e.g. `cargo run --no-default-features --features ndarray`"
+);
+
+#[cfg(feature = "rocm")]
+type TrainingBackend = Autodiff<Rocm>;
+#[cfg(feature = "cuda")]
+type TrainingBackend = Autodiff<Cuda>;
+#[cfg(feature = "wgpu")]
+type TrainingBackend = Autodiff<Wgpu>;
+#[cfg(feature = "ndarray")]
+type TrainingBackend = Autodiff<NdArray>;
+type InferenceBackend = <TrainingBackend as AutodiffBackend>::InnerBackend;
+
 fn main() -> Result<(), Box<dyn Error>> {
-    #[cfg(feature = "rocm")]
-    let (actions_tx, messages_rx, _) = TrainingThread::<Autodiff<Rocm>>::spawn_thread();
-    #[cfg(feature = "cuda")]
-    let (actions_tx, messages_rx, _) = TrainingThread::<Autodiff<Cuda>>::spawn_thread();
-    #[cfg(feature = "wgpu")]
-    let (actions_tx, messages_rx, _) = TrainingThread::<Autodiff<Wgpu>>::spawn_thread();
+    match CliCommand::from_args(env::args().skip(1))? {
+        CliCommand::Window(options) => run_window(options),
+        CliCommand::Headless(options) => HeadlessTraining::<TrainingBackend>::new(options)?.run(),
+        CliCommand::Replay(file_path) => EpisodeReplay::new(file_path).run(),
+        CliCommand::Evaluate(model_path, options) => {
+            ModelEvaluation::<InferenceBackend>::new(model_path, options).run()
+        }
+        CliCommand::Search(search, options) => {
+            SearchEvaluation::<InferenceBackend>::new(search, options).run()
+        }
+        CliCommand::Benchmark(options) => AgentBenchmark::<InferenceBackend>::new(options).run(),
+    }
+}
+
+fn run_window(options: WindowOptions) -> Result<(), Box<dyn Error>> {
+    let hyperparameters =
+        Hyperparameters::load(options.config_path.as_deref(), &options.overrides)?;
+    let (actions_tx, messages_rx, training_handle) =
+        TrainingThread::<TrainingBackend>::spawn_thread(hyperparameters.clone());
 
     let ui = AppWindow::new()?;
     let ui_handle = ui.as_weak();
 
-    let (updates_tx, _, epochs_per_second) =
+    let (updates_tx, overview_handle, epochs_per_second) =
         TrainingOverviewThread::spawn_thread(ui_handle.clone());
-    let _ = TrainingUpdateAdapter::spawn_thread(messages_rx, updates_tx.clone());
+    let (agent_tx, agent_handle) = AgentThread::<InferenceBackend>::spawn_thread(ui_handle.clone());
+    let adapter_handle =
+        TrainingUpdateAdapter::spawn_thread(messages_rx, updates_tx.clone(), agent_tx.clone());
 
-    setup_actions(actions_tx, &ui, updates_tx.clone());
-    setup_plots(&ui, updates_tx);
+    setup_hyperparameters(actions_tx.clone(), &ui, &hyperparameters);
+    setup_agent_playback(actions_tx.clone(), agent_tx.clone(), &ui);
+    setup_actions(actions_tx.clone(), &ui, updates_tx.clone());
+    setup_plots(&ui, updates_tx.clone());
+    setup_game(agent_tx.clone(), &ui);
     start_plots_area_update_timer(ui_handle.clone());
     let _timer = start_epochs_per_second_timer(epochs_per_second, ui_handle.clone());
     setup_formatters(ui_handle.clone());
 
     ui.run()?;
 
+    if ui.global::<UiTrainingStats>().get_state() == UiTrainingState::Training {
+        println!("Finishing the current epoch before quitting");
+    }
+    // The threads updating the window stop first. Training then finishes its current epoch and
+    // saves a checkpoint if asked to, and the adapter ends along with it.
+    let _ = updates_tx.send(TrainingOverviewUpdate::Shutdown);
+    let _ = agent_tx.send(AgentAction::Shutdown);
+    let _ = actions_tx.send(TrainingAction::Shutdown(options.autosave_directory));
+    for (name, handle) in [
+        ("training overview", overview_handle),
+        ("agent", agent_handle),
+        ("training", training_handle),
+        ("training update adapter", adapter_handle),
+    ] {
+        if handle.join().is_err() {
+            eprintln!("The {} thread panicked", name);
+        }
+    }
+
     Ok(())
 }
 
@@ -68,13 +151,13 @@ fn setup_actions(
     actions.on_start_training({
         let action_tx = actions_tx.clone();
         move || {
-            action_tx.send(TrainingAction::Start).unwrap();
+            send_to_thread(&action_tx, TrainingAction::Start);
         }
     });
     actions.on_pause_training({
         let action_tx = actions_tx.clone();
         move || {
-            action_tx.send(TrainingAction::Pause).unwrap();
+            send_to_thread(&action_tx, TrainingAction::Pause);
         }
     });
     actions.on_save_model({
@@ -83,7 +166,7 @@ fn setup_actions(
             let Some(file) = FileDialog::new().save_file() else {
                 return;
             };
-            action_tx.send(TrainingAction::Save(file)).unwrap();
+            send_to_thread(&action_tx, TrainingAction::Save(file));
         }
     });
     actions.on_load_model({
@@ -92,7 +175,34 @@ fn setup_actions(
             let Some(file) = FileDialog::new().pick_file() else {
                 return;
             };
-            action_tx.send(TrainingAction::Load(file)).unwrap();
+            send_to_thread(&action_tx, TrainingAction::Load(file));
+        }
+    });
+    actions.on_save_checkpoint({
+        let action_tx = actions_tx.clone();
+        move || {
+            let Some(directory) = FileDialog::new().pick_folder() else {
+                return;
+            };
+            send_to_thread(&action_tx, TrainingAction::SaveCheckpoint(directory));
+        }
+    });
+    actions.on_load_checkpoint({
+        let action_tx = actions_tx.clone();
+        move || {
+            let Some(directory) = FileDialog::new().pick_folder() else {
+                return;
+            };
+            send_to_thread(&action_tx, TrainingAction::LoadCheckpoint(directory));
+        }
+    });
+    actions.on_export_metrics({
+        let action_tx = actions_tx.clone();
+        move || {
+            let Some(directory) = FileDialog::new().pick_folder() else {
+                return;
+            };
+            send_to_thread(&action_tx, TrainingAction::ExportMetrics(directory));
         }
     });
     actions.on_quit(|| {
@@ -100,6 +210,140 @@ fn setup_actions(
     });
 }
 
+fn setup_hyperparameters(
+    actions_tx: Sender<TrainingAction>,
+    ui: &AppWindow,
+    hyperparameters: &Hyperparameters,
+) {
+    let settings = ui.global::<HyperparametersSettings>();
+    let ui_handle = ui.as_weak();
+
+    settings.set_values(hyperparameters_model(hyperparameters));
+    settings.on_edited({
+        let ui_handle = ui_handle.clone();
+        move |index, value| {
+            let ui = ui_handle.unwrap();
+            let values = ui.global::<HyperparametersSettings>().get_values();
+            if let Some(mut row) = values.row_data(index as usize) {
+                row.value = value;
+                values.set_row_data(index as usize, row);
+            }
+        }
+    });
+    settings.on_apply(move || {
+        let ui = ui_handle.unwrap();
+        let settings = ui.global::<HyperparametersSettings>();
+        match hyperparameters_from_model(&settings.get_values()) {
+            Ok(hyperparameters) => {
+                settings.set_error("".into());
+                send_to_thread(&actions_tx, TrainingAction::SetHyperparameters(hyperparameters));
+            }
+            Err(error) => settings.set_error(error.to_string().into()),
+        }
+    });
+}
+
+fn setup_game(agent_tx: Sender<AgentAction>, ui: &AppWindow) {
+    let game = ui.global::<Game>();
+    let ui_handle = ui.as_weak();
+    let human_game = Rc::new(RefCell::new(HumanGame::new(agent_tx)));
+
+    human_game.borrow().show(&game);
+    game.on_move_tiles({
+        let ui_handle = ui_handle.clone();
+        let human_game = human_game.clone();
+        move |direction| {
+            let ui = ui_handle.unwrap();
+            human_game
+                .borrow_mut()
+                .move_tiles(&ui.global::<Game>(), direction.as_direction());
+        }
+    });
+    game.on_new_game({
+        let ui_handle = ui_handle.clone();
+        let human_game = human_game.clone();
+        move || {
+            let ui = ui_handle.unwrap();
+            human_game.borrow_mut().new_game(&ui.global::<Game>());
+        }
+    });
+    game.on_hint_toggled({
+        let ui_handle = ui_handle.clone();
+        let human_game = human_game.clone();
+        move || {
+            let ui = ui_handle.unwrap();
+            human_game.borrow().request_hint(&ui.global::<Game>(), false);
+        }
+    });
+    game.on_autoplay({
+        let ui_handle = ui_handle.clone();
+        let human_game = human_game.clone();
+        move || {
+            let ui = ui_handle.unwrap();
+            let game = ui.global::<Game>();
+            let num_moves = game.get_autoplay_moves().max(0) as usize;
+            human_game.borrow_mut().autoplay(&game, num_moves);
+        }
+    });
+    game.on_autoplay_move(move |direction| {
+        let ui = ui_handle.unwrap();
+        human_game
+            .borrow_mut()
+            .autoplay_move(&ui.global::<Game>(), direction.as_direction());
+    });
+}
+
+fn setup_agent_playback(
+    actions_tx: Sender<TrainingAction>,
+    agent_tx: Sender<AgentAction>,
+    ui: &AppWindow,
+) {
+    let playback = ui.global::<AgentPlayback>();
+    let ui_handle = ui.as_weak();
+
+    playback.on_use_current_model(move || {
+        send_to_thread(&actions_tx, TrainingAction::ShareModel);
+    });
+    playback.on_load_model({
+        let agent_tx = agent_tx.clone();
+        move || {
+            let Some(file) = FileDialog::new().pick_file() else {
+                return;
+            };
+            send_to_thread(&agent_tx, AgentAction::LoadModel(file));
+        }
+    });
+    playback.on_play({
+        let agent_tx = agent_tx.clone();
+        move || {
+            send_to_thread(&agent_tx, AgentAction::Play);
+        }
+    });
+    playback.on_pause({
+        let agent_tx = agent_tx.clone();
+        move || {
+            send_to_thread(&agent_tx, AgentAction::Pause);
+        }
+    });
+    playback.on_step({
+        let agent_tx = agent_tx.clone();
+        move || {
+            send_to_thread(&agent_tx, AgentAction::Step);
+        }
+    });
+    playback.on_new_game({
+        let agent_tx = agent_tx.clone();
+        move || {
+            send_to_thread(&agent_tx, AgentAction::NewGame);
+        }
+    });
+    playback.on_speed_changed(move || {
+        let ui = ui_handle.unwrap();
+        let moves_per_second = ui.global::<AgentPlayback>().get_moves_per_second();
+        send_to_thread(&agent_tx, AgentAction::SetSpeed(moves_per_second));
+    });
+}
+
 fn setup_plots(ui: &AppWindow, updates_tx: Sender<TrainingOverviewUpdate>) {
     let plots = ui.global::<Plots>();
     let ui_handle = ui.as_weak();
@@ -110,9 +354,10 @@ fn setup_plots(ui: &AppWindow, updates_tx: Sender<TrainingOverviewUpdate>) {
             let ui = ui_handle.unwrap();
             let plots = ui.global::<Plots>();
             let area_size = plots.get_plots_area_size();
-
-            let sizes = PlotsSizes::from(area_size);
-            updates_tx.send(PlotsSizesChanged(sizes)).unwrap();
+            send_to_thread(
+                &updates_tx,
+                TrainingOverviewUpdate::PlotsAreaSizeChanged(area_size),
+            );
         }
     });
     plots.on_range_settings_changed({
@@ -120,9 +365,7 @@ fn setup_plots(ui: &AppWindow, updates_tx: Sender<TrainingOverviewUpdate>) {
             let ui = ui_handle.unwrap();
             let plots = ui.global::<Plots>();
             if let Some(settings) = build_plot_settings(&plots) {
-                updates_tx
-                    .send(TrainingOverviewUpdate::PlotsSettingsChanged(settings))
-                    .unwrap();
+                send_to_thread(&updates_tx, TrainingOverviewUpdate::PlotsSettingsChanged(settings));
             }
         }
     });
@@ -149,6 +392,7 @@ fn build_plot_settings(plots: &Plots) -> Option<PlotsSettings> {
         return Some(PlotsSettings {
             is_log_scale_enabled: plots.get_log_scale(),
             range,
+            show_diagnostics: plots.get_show_diagnostics(),
         });
     } else {
         None
@@ -177,6 +421,13 @@ fn start_epochs_per_second_timer(
     timer
 }
 
+// The threads only stop before the window closes if they panicked, which is reported on their own
+fn send_to_thread<T>(sender: &Sender<T>, message: T) {
+    if sender.send(message).is_err() {
+        eprintln!("Couldn't reach a stopped background thread, dropping the UI action");
+    }
+}
+
 fn setup_formatters(ui_handle: Weak<AppWindow>) {
     let ui = ui_handle.unwrap();
     let formatters = ui.global::<Formatters>();
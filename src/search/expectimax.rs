@@ -0,0 +1,226 @@
+use crate::game::{
+    bit_board::{TILE_BITS, exponent_at, moved_cells},
+    board::{Direction, NUM_TILES},
+};
+
+// New tiles are 2 (exponent 1) with p=0.9 and 4 (exponent 2) with p=0.1, like in RealGameRng
+const NEW_TILES: [(u64, f32); 2] = [(1, 0.9), (2, 0.1)];
+
+// Scores the boards at the leaves of the search, higher is better. Boards are packed like
+// BitBoard cells. They're evaluated in groups, so that models can run them as one batch.
+pub(crate) trait LeafEvaluator {
+    fn evaluate(&self, boards: &[u64]) -> Vec<f32>;
+
+    // Value of boards with no possible move
+    fn game_over_value(&self) -> f32 {
+        0.0
+    }
+
+    // Reward of the move from `cells` to `child`, which already holds the new tile. Values that
+    // only cover the future, like the state values of a model, need the rewards along the path.
+    fn reward(&self, _cells: u64, _direction: Direction, _child: u64, _score_gain: u32) -> f32 {
+        0.0
+    }
+
+    // Weight of the value after a move relative to its reward
+    fn discount_factor(&self) -> f32 {
+        1.0
+    }
+}
+
+// Depth-limited expectimax: the player picks the move with the best expected value over all
+// possible new tiles, searching `depth` moves ahead before evaluating the boards.
+pub(crate) struct Expectimax<E: LeafEvaluator> {
+    evaluator: E,
+    depth: usize,
+}
+
+impl<E: LeafEvaluator> Expectimax<E> {
+    pub(crate) fn new(evaluator: E, depth: usize) -> Self {
+        Expectimax {
+            evaluator,
+            depth: depth.max(1),
+        }
+    }
+
+    // None when the game is over
+    pub(crate) fn best_direction(&self, cells: u64) -> Option<Direction> {
+        self.move_values(cells)
+            .into_iter()
+            .max_by(|lhs, rhs| lhs.1.total_cmp(&rhs.1))
+            .map(|(direction, _)| direction)
+    }
+
+    // Expected value of every possible move
+    pub(crate) fn move_values(&self, cells: u64) -> Vec<(Direction, f32)> {
+        Direction::ALL
+            .into_iter()
+            .filter_map(|direction| {
+                let (moved, score_gain) = moved_cells(cells, direction);
+                (moved != cells).then(|| {
+                    let value =
+                        self.chance_value(cells, direction, moved, score_gain, self.depth - 1);
+                    (direction, value)
+                })
+            })
+            .collect()
+    }
+
+    fn max_value(&self, cells: u64, depth: usize) -> f32 {
+        Direction::ALL
+            .into_iter()
+            .filter_map(|direction| {
+                let (moved, score_gain) = moved_cells(cells, direction);
+                (moved != cells)
+                    .then(|| self.chance_value(cells, direction, moved, score_gain, depth - 1))
+            })
+            .reduce(f32::max)
+            .unwrap_or_else(|| self.evaluator.game_over_value())
+    }
+
+    // Value of the move from `cells` to `moved`, before the new tile is placed
+    fn chance_value(
+        &self,
+        cells: u64,
+        direction: Direction,
+        moved: u64,
+        score_gain: u32,
+        depth: usize,
+    ) -> f32 {
+        let empty_positions: Vec<usize> = (0..NUM_TILES)
+            .filter(|index| exponent_at(moved, *index) == 0)
+            .collect();
+        let children: Vec<(u64, f32)> = empty_positions
+            .iter()
+            .flat_map(|index| {
+                NEW_TILES.map(|(exponent, probability)| {
+                    let child = moved | (exponent << (TILE_BITS * index));
+                    (child, probability / empty_positions.len() as f32)
+                })
+            })
+            .collect();
+
+        let values = if depth == 0 {
+            let boards: Vec<u64> = children.iter().map(|(child, _)| *child).collect();
+            self.evaluator.evaluate(&boards)
+        } else {
+            children
+                .iter()
+                .map(|(child, _)| self.max_value(*child, depth))
+                .collect()
+        };
+
+        let discount_factor = self.evaluator.discount_factor();
+        children
+            .iter()
+            .zip(values)
+            .map(|((child, probability), value)| {
+                let reward = self.evaluator.reward(cells, direction, *child, score_gain);
+                probability * (reward + discount_factor * value)
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct EmptyTilesEvaluator {}
+
+    impl LeafEvaluator for EmptyTilesEvaluator {
+        fn evaluate(&self, boards: &[u64]) -> Vec<f32> {
+            boards
+                .iter()
+                .map(|cells| {
+                    (0..NUM_TILES)
+                        .filter(|index| exponent_at(*cells, *index) == 0)
+                        .count() as f32
+                })
+                .collect()
+        }
+    }
+
+    struct FourEvaluator {}
+
+    impl LeafEvaluator for FourEvaluator {
+        fn evaluate(&self, boards: &[u64]) -> Vec<f32> {
+            boards
+                .iter()
+                .map(|cells| {
+                    let has_four = (0..NUM_TILES).any(|index| exponent_at(*cells, index) == 2);
+                    if has_four { 1.0 } else { 0.0 }
+                })
+                .collect()
+        }
+    }
+
+    // Every leaf is worth 2 and every move its score gain
+    struct ScoreGainEvaluator {}
+
+    impl LeafEvaluator for ScoreGainEvaluator {
+        fn evaluate(&self, boards: &[u64]) -> Vec<f32> {
+            vec![2.0; boards.len()]
+        }
+
+        fn reward(&self, _cells: u64, _direction: Direction, _child: u64, score_gain: u32) -> f32 {
+            score_gain as f32
+        }
+
+        fn discount_factor(&self) -> f32 {
+            0.5
+        }
+    }
+
+    #[test]
+    fn move_rewards_are_added_to_the_discounted_values() {
+        // 2 2 . . in the first row, nothing else
+        let cells = 0x0011;
+        let search = Expectimax::new(ScoreGainEvaluator {}, 1);
+
+        for (direction, value) in search.move_values(cells) {
+            let (_, score_gain) = moved_cells(cells, direction);
+            assert!((value - (score_gain as f32 + 0.5 * 2.0)).abs() < 1e-6);
+        }
+        assert_eq!(
+            search
+                .best_direction(cells)
+                .map(|direction| moved_cells(cells, direction).1),
+            Some(4)
+        );
+    }
+
+    #[test]
+    fn merging_move_is_picked() {
+        // 2 2 . . in the first row, nothing else
+        let cells = 0x0011;
+        let search = Expectimax::new(EmptyTilesEvaluator {}, 2);
+
+        let direction = search.best_direction(cells);
+
+        assert!(matches!(
+            direction,
+            Some(Direction::Left) | Some(Direction::Right)
+        ));
+        assert_eq!(search.move_values(cells).len(), 3);
+    }
+
+    #[test]
+    fn new_tiles_are_weighted_by_probability() {
+        let search = Expectimax::new(FourEvaluator {}, 1);
+        // Every tile is a 2 except the last one, which is empty
+        let cells = 0x0111_1111_1111_1111;
+
+        assert!((search.chance_value(cells, Direction::Up, cells, 0, 0) - 0.1).abs() < 1e-6);
+    }
+
+    #[test]
+    fn game_over_board_has_no_move() {
+        // 2 4 2 4 / 4 2 4 2 / ...
+        let cells = 0x1212_2121_1212_2121;
+        let search = Expectimax::new(EmptyTilesEvaluator {}, 2);
+
+        assert_eq!(search.best_direction(cells), None);
+        assert_eq!(search.max_value(cells, 1), 0.0);
+    }
+}
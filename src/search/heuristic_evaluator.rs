@@ -0,0 +1,113 @@
+use std::sync::LazyLock;
+
+use crate::{
+    game::{
+        bit_board::{ROW_BITS, TILE_BITS, TILE_MASK, transpose},
+        board::NUM_ROWS,
+    },
+    search::expectimax::LeafEvaluator,
+};
+
+// Weights of the classic hand-tuned 2048 heuristic. Every row and column adds LOST_PENALTY,
+// which keeps values of boards that aren't over above game_over_value().
+const LOST_PENALTY: f32 = 200000.0;
+const EMPTY_WEIGHT: f32 = 270.0;
+const MERGES_WEIGHT: f32 = 700.0;
+const MONOTONICITY_POWER: f32 = 4.0;
+const MONOTONICITY_WEIGHT: f32 = 47.0;
+const SUM_POWER: f32 = 3.5;
+const SUM_WEIGHT: f32 = 11.0;
+
+static ROW_VALUES: LazyLock<Vec<f32>> = LazyLock::new(|| {
+    (0..1 << ROW_BITS)
+        .map(|row| row_value(row as u16))
+        .collect()
+});
+
+// Rewards empty tiles, possible merges and rows and columns whose tiles only grow or shrink
+// in one direction, and penalizes large tiles away from such orderings.
+#[derive(Default)]
+pub(crate) struct HeuristicEvaluator {}
+
+impl HeuristicEvaluator {
+    pub(crate) fn value(cells: u64) -> f32 {
+        let rows_value: f32 = (0..NUM_ROWS)
+            .map(|row| ROW_VALUES[((cells >> (ROW_BITS * row)) & 0xffff) as usize])
+            .sum();
+        let transposed = transpose(cells);
+        let columns_value: f32 = (0..NUM_ROWS)
+            .map(|row| ROW_VALUES[((transposed >> (ROW_BITS * row)) & 0xffff) as usize])
+            .sum();
+
+        rows_value + columns_value
+    }
+}
+
+impl LeafEvaluator for HeuristicEvaluator {
+    fn evaluate(&self, boards: &[u64]) -> Vec<f32> {
+        boards.iter().map(|cells| Self::value(*cells)).collect()
+    }
+}
+
+fn row_value(row: u16) -> f32 {
+    let exponents: Vec<f32> = (0..4)
+        .map(|column| ((row as u64 >> (TILE_BITS * column)) & TILE_MASK) as f32)
+        .collect();
+
+    let mut sum = 0.0;
+    let mut empty = 0;
+    let mut merges = 0;
+    let mut previous = 0.0;
+    let mut counter = 0;
+    for exponent in &exponents {
+        sum += exponent.powf(SUM_POWER);
+        if *exponent == 0.0 {
+            empty += 1;
+        } else {
+            if previous == *exponent {
+                counter += 1;
+            } else if counter > 0 {
+                merges += 1 + counter;
+                counter = 0;
+            }
+            previous = *exponent;
+        }
+    }
+    if counter > 0 {
+        merges += 1 + counter;
+    }
+
+    let mut monotonicity_left = 0.0;
+    let mut monotonicity_right = 0.0;
+    for pair in exponents.windows(2) {
+        let left = pair[0].powf(MONOTONICITY_POWER);
+        let right = pair[1].powf(MONOTONICITY_POWER);
+        if pair[0] > pair[1] {
+            monotonicity_left += left - right;
+        } else {
+            monotonicity_right += right - left;
+        }
+    }
+
+    LOST_PENALTY + EMPTY_WEIGHT * empty as f32 + MERGES_WEIGHT * merges as f32
+        - MONOTONICITY_WEIGHT * f32::min(monotonicity_left, monotonicity_right)
+        - SUM_WEIGHT * sum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ordered_board_is_preferred() {
+        // 2 4 8 16 in the first row, everything else empty
+        let ordered = 0x4321;
+        // The same tiles, shuffled
+        let shuffled = 0x2413;
+
+        assert!(HeuristicEvaluator::value(ordered) > HeuristicEvaluator::value(shuffled));
+        assert!(
+            HeuristicEvaluator::value(shuffled) > HeuristicEvaluator::default().game_over_value()
+        );
+    }
+}
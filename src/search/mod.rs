@@ -0,0 +1,3 @@
+pub(crate) mod expectimax;
+pub(crate) mod heuristic_evaluator;
+pub(crate) mod model_evaluator;
@@ -0,0 +1,88 @@
+use burn::{
+    Tensor,
+    prelude::Backend,
+    tensor::{Device, TensorData},
+};
+
+use crate::{
+    dqn::{
+        critic::{CriticType, total_reward},
+        hyperparameters::Hyperparameters,
+        model::Model,
+        state::StateType,
+    },
+    game::{
+        bit_board::{is_game_over, unpacked_tiles},
+        board::{Board, Direction},
+        game_rng::RealGameRng,
+    },
+    search::expectimax::LeafEvaluator,
+    training::{game_model::GameModel, training_critic::TrainingCritic},
+};
+
+// Uses the state values of a trained model, so that the search adds lookahead to the network.
+// State values only estimate the discounted rewards still to come, so the moves searched are
+// rewarded and discounted like in training.
+pub(crate) struct ModelEvaluator<B: Backend> {
+    model: GameModel<B>,
+    critic: TrainingCritic,
+    discount_factor: f32,
+    device: Device<B>,
+}
+
+impl<B: Backend> ModelEvaluator<B> {
+    // The hyperparameters should be the ones the model was trained with
+    pub(crate) fn new(
+        model: GameModel<B>,
+        hyperparameters: &Hyperparameters,
+        device: Device<B>,
+    ) -> Self {
+        ModelEvaluator {
+            model,
+            critic: TrainingCritic::new(hyperparameters),
+            discount_factor: hyperparameters.discount_factor,
+            device,
+        }
+    }
+}
+
+impl<B: Backend> LeafEvaluator for ModelEvaluator<B> {
+    // Lost games have no rewards left, their reward already holds the loss
+    fn evaluate(&self, boards: &[u64]) -> Vec<f32> {
+        let num_features = Board::<RealGameRng>::NUM_FEATURES;
+        let mut features = Vec::with_capacity(boards.len() * num_features);
+        for cells in boards {
+            Board::<RealGameRng>::extend_with_features(cells, &mut features);
+        }
+
+        let data = TensorData::new(features, [boards.len(), num_features]);
+        let input = Tensor::<B, 2>::from_data(data, &self.device);
+        let values: Vec<f32> = self
+            .model
+            .state_values(input)
+            .into_data()
+            .into_vec()
+            .unwrap();
+
+        boards
+            .iter()
+            .zip(values)
+            .map(|(cells, value)| if is_game_over(*cells) { 0.0 } else { value })
+            .collect()
+    }
+
+    fn reward(&self, cells: u64, direction: Direction, child: u64, score_gain: u32) -> f32 {
+        let state = unpacked_board(cells, 0);
+        let next_state = unpacked_board(child, score_gain);
+        total_reward(&self.critic.reward_terms(&state, &direction, &next_state))
+    }
+
+    fn discount_factor(&self) -> f32 {
+        self.discount_factor
+    }
+}
+
+// The critic only looks at the tiles and the score gained, the random generator isn't used
+fn unpacked_board(cells: u64, score: u32) -> Board<RealGameRng> {
+    Board::new_with_tiles_and_score(unpacked_tiles(cells), score, RealGameRng::new_with_seed(0))
+}
@@ -1,7 +1,8 @@
 use crate::{
     dqn::state::{ActionType, StateType},
     game::{
-        board::{Board, Direction, NUM_COLUMNS, NUM_ROWS, NUM_TILES},
+        bit_board::{BitBoard, MAX_EXPONENT, TILE_BITS, exponent_at},
+        board::{Board, Direction, NUM_TILES, Tile},
         game_rng::RealGameRng,
     },
 };
@@ -17,16 +18,23 @@ impl ActionType for Direction {
     }
 }
 
+const FEATURES_PER_TILE: usize = 12;
+
 impl StateType for Board<RealGameRng> {
     type Action = Direction;
+    type Packed = u64;
 
     const NUM_ACTIONS: usize = 4;
-    const NUM_FEATURES: usize = NUM_TILES * 12;
+    const NUM_FEATURES: usize = NUM_TILES * FEATURES_PER_TILE;
 
     fn initial_state() -> Board<RealGameRng> {
         Board::new()
     }
 
+    fn initial_state_with_seed(seed: u64) -> Board<RealGameRng> {
+        Board::new_with_seed(seed)
+    }
+
     fn possible_actions(&self) -> Vec<Self::Action> {
         let mut actions = Vec::with_capacity(Self::NUM_ACTIONS);
 
@@ -48,12 +56,7 @@ impl StateType for Board<RealGameRng> {
 
     fn advance(&self, action: &Self::Action) -> Self {
         let mut board = self.clone();
-        match action {
-            Direction::Up => board.move_up(),
-            Direction::Down => board.move_down(),
-            Direction::Left => board.move_left(),
-            Direction::Right => board.move_right(),
-        }
+        board.move_in_direction(*action);
         board
     }
 
@@ -62,19 +65,123 @@ impl StateType for Board<RealGameRng> {
     }
 
     fn as_features(&self) -> Vec<f32> {
-        // TODO: Fill existing buffer instead of creating new one
         let mut features = Vec::with_capacity(Self::NUM_FEATURES);
-        for row in 0..NUM_ROWS {
-            for column in 0..NUM_COLUMNS {
-                let tile_value = self.value_at(row, column).unwrap_or_default();
-                let mut inputs = vec![0.0; 12];
-                if tile_value > 0 {
-                    let index = tile_value.ilog2().min((inputs.len() - 1) as u32) as usize;
-                    inputs[index] = 1.0;
+        Self::extend_with_features(&self.pack(), &mut features);
+        features
+    }
+
+    // 4 bits per tile holding the exponent of its value, 0 for empty tiles. Exponents above 15
+    // are clamped, which doesn't change the features since those saturate at 2048 anyway.
+    fn pack(&self) -> u64 {
+        self.tiles()
+            .iter()
+            .enumerate()
+            .fold(0, |packed, (index, tile)| match tile {
+                Tile::Empty => packed,
+                Tile::Value(value) => {
+                    packed | (value.ilog2().min(MAX_EXPONENT as u32) as u64) << (TILE_BITS * index)
                 }
-                features.extend(inputs);
-            }
-        }
+            })
+    }
+
+    fn extend_with_features(packed: &u64, features: &mut Vec<f32>) {
+        extend_with_tile_features(*packed, features);
+    }
+}
+
+// Uses the same features as Board, so models can be shared between both
+impl StateType for BitBoard<RealGameRng> {
+    type Action = Direction;
+    type Packed = u64;
+
+    const NUM_ACTIONS: usize = 4;
+    const NUM_FEATURES: usize = NUM_TILES * FEATURES_PER_TILE;
+
+    fn initial_state() -> BitBoard<RealGameRng> {
+        BitBoard::new()
+    }
+
+    fn initial_state_with_seed(seed: u64) -> BitBoard<RealGameRng> {
+        BitBoard::new_with_seed(seed)
+    }
+
+    fn possible_actions(&self) -> Vec<Self::Action> {
+        Direction::ALL
+            .into_iter()
+            .filter(|direction| match direction {
+                Direction::Up => self.can_move_up(),
+                Direction::Down => self.can_move_down(),
+                Direction::Left => self.can_move_left(),
+                Direction::Right => self.can_move_right(),
+            })
+            .collect()
+    }
+
+    fn advance(&self, action: &Self::Action) -> Self {
+        let mut board = self.clone();
+        board.move_in_direction(*action);
+        board
+    }
+
+    fn is_terminal(&self) -> bool {
+        self.is_over()
+    }
+
+    fn as_features(&self) -> Vec<f32> {
+        let mut features = Vec::with_capacity(Self::NUM_FEATURES);
+        extend_with_tile_features(self.cells(), &mut features);
         features
     }
+
+    fn pack(&self) -> u64 {
+        self.cells()
+    }
+
+    fn extend_with_features(packed: &u64, features: &mut Vec<f32>) {
+        extend_with_tile_features(*packed, features);
+    }
+}
+
+fn extend_with_tile_features(packed: u64, features: &mut Vec<f32>) {
+    for index in 0..NUM_TILES {
+        let exponent = exponent_at(packed, index);
+        let mut inputs = [0.0; FEATURES_PER_TILE];
+        if exponent > 0 {
+            inputs[(exponent as usize).min(FEATURES_PER_TILE - 1)] = 1.0;
+        }
+        features.extend(inputs);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::board::Tile::{Empty, Value};
+
+    #[test]
+    fn packed_board_expands_to_one_hot_features() {
+        let mut tiles = [Empty; NUM_TILES];
+        tiles[0] = Value(2);
+        tiles[5] = Value(64);
+        tiles[15] = Value(65536);
+        let board = Board::new_with_tiles(tiles, RealGameRng::new());
+
+        let features = board.as_features();
+
+        assert_eq!(features.len(), Board::<RealGameRng>::NUM_FEATURES);
+        let hot_features: Vec<usize> = features
+            .iter()
+            .enumerate()
+            .filter(|(_, feature)| **feature == 1.0)
+            .map(|(index, _)| index)
+            .collect();
+        assert_eq!(
+            hot_features,
+            vec![1, 5 * FEATURES_PER_TILE + 6, 15 * FEATURES_PER_TILE + 11]
+        );
+        assert_eq!(
+            BitBoard::new_with_tiles(tiles, RealGameRng::new()).as_features(),
+            features
+        );
+    }
 }
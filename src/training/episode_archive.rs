@@ -0,0 +1,119 @@
+use std::cmp::Reverse;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::{
+    dqn::{episode_recorder::EpisodeRecorderType, state::StateType},
+    game::{board::Board, episode::Episode, game_rng::RealGameRng},
+};
+
+pub(crate) const EPISODE_FILE_EXTENSION: &str = "2048";
+
+struct ArchivedEpisode {
+    score: u32,
+    file_path: PathBuf,
+}
+
+// Records every played game and keeps the best scoring ones on disk.
+#[derive(Default)]
+pub(crate) struct EpisodeArchive {
+    directory: Option<PathBuf>,
+    capacity: usize,
+    archived_episodes: Vec<ArchivedEpisode>,
+    current_episode: Option<Episode>,
+    episode_number: usize,
+}
+
+impl EpisodeArchive {
+    pub(crate) fn new(directory: PathBuf, capacity: usize) -> EpisodeArchive {
+        EpisodeArchive {
+            directory: Some(directory),
+            capacity,
+            ..Default::default()
+        }
+    }
+
+    fn archive(&mut self, episode: &Episode) {
+        let Some(directory) = &self.directory else {
+            return;
+        };
+        if self.capacity == 0 {
+            return;
+        }
+
+        let worst_score = self
+            .archived_episodes
+            .iter()
+            .map(|archived| archived.score)
+            .min()
+            .unwrap_or_default();
+        if self.archived_episodes.len() >= self.capacity && episode.final_score <= worst_score {
+            return;
+        }
+
+        let file_name = format!(
+            "episode-{:08}-score-{}.{}",
+            self.episode_number, episode.final_score, EPISODE_FILE_EXTENSION
+        );
+        let file_path = directory.join(file_name);
+        if let Err(error) = fs::create_dir_all(directory).and_then(|_| episode.save(&file_path)) {
+            eprintln!(
+                "Failed to archive episode {}: {}",
+                self.episode_number, error
+            );
+            return;
+        }
+
+        self.archived_episodes.push(ArchivedEpisode {
+            score: episode.final_score,
+            file_path,
+        });
+        self.archived_episodes
+            .sort_by_key(|archived| Reverse(archived.score));
+        while self.archived_episodes.len() > self.capacity {
+            let removed = self.archived_episodes.pop().unwrap();
+            if let Err(error) = fs::remove_file(&removed.file_path) {
+                eprintln!(
+                    "Failed to remove episode {}: {}",
+                    removed.file_path.display(),
+                    error
+                );
+            }
+        }
+    }
+}
+
+impl EpisodeRecorderType for EpisodeArchive {
+    type State = Board<RealGameRng>;
+
+    fn record_initial_state(&mut self, state: &Self::State) {
+        self.episode_number += 1;
+        self.current_episode = Some(Episode::new(state));
+    }
+
+    fn record_step(
+        &mut self,
+        action: &<Self::State as StateType>::Action,
+        reward: f32,
+        next_state: &Self::State,
+    ) {
+        let Some(episode) = self.current_episode.as_mut() else {
+            return;
+        };
+        // The rest of the episode couldn't be replayed, so it isn't archived
+        if let Err(error) = episode.record_step(*action, reward, next_state) {
+            eprintln!(
+                "Failed to record episode {}: {}",
+                self.episode_number, error
+            );
+            self.current_episode = None;
+        }
+    }
+
+    fn record_final_state(&mut self, _state: &Self::State) {
+        let Some(episode) = self.current_episode.take() else {
+            return;
+        };
+        self.archive(&episode);
+    }
+}
@@ -0,0 +1,126 @@
+use std::fmt::{Display, Formatter};
+
+use crate::dqn::evaluator::EvaluationRecorderType;
+use crate::game::board::Board;
+use crate::game::game_rng::RealGameRng;
+
+pub(crate) const WIN_TILES: [u32; 3] = [2048, 4096, 8192];
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct EvaluationStats {
+    pub games: usize,
+    pub mean_score: f64,
+    pub median_score: f64,
+    pub max_score: u32,
+    pub win_rates: [f64; WIN_TILES.len()],
+    pub mean_game_length: f64,
+}
+
+impl Display for EvaluationStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} games: mean score {:.1}, median score {:.1}, max score {}, mean length {:.1}",
+            self.games, self.mean_score, self.median_score, self.max_score, self.mean_game_length
+        )?;
+        for (tile, win_rate) in WIN_TILES.iter().zip(self.win_rates) {
+            write!(f, ", {} reached {:.1}%", tile, win_rate * 100.0)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct EvaluationStatsRecorder {
+    scores: Vec<u32>,
+    best_tiles: Vec<u32>,
+    game_lengths: Vec<u32>,
+}
+
+impl EvaluationRecorderType for EvaluationStatsRecorder {
+    type Stats = EvaluationStats;
+    type State = Board<RealGameRng>;
+
+    fn record_game(&mut self, final_state: &Self::State, game_length: u32) {
+        self.scores.push(final_state.score);
+        self.best_tiles.push(final_state.max_tile_value());
+        self.game_lengths.push(game_length);
+    }
+
+    fn stats(&self) -> Self::Stats {
+        let games = self.scores.len();
+        let mean = |values: &[u32]| {
+            if values.is_empty() {
+                0.0
+            } else {
+                values.iter().map(|value| *value as f64).sum::<f64>() / values.len() as f64
+            }
+        };
+
+        let mut sorted_scores = self.scores.clone();
+        sorted_scores.sort_unstable();
+        let median_score = match games {
+            0 => 0.0,
+            _ if games % 2 == 1 => sorted_scores[games / 2] as f64,
+            _ => (sorted_scores[games / 2 - 1] as f64 + sorted_scores[games / 2] as f64) / 2.0,
+        };
+
+        let win_rates = WIN_TILES.map(|tile| {
+            let wins = self.best_tiles.iter().filter(|best| **best >= tile).count();
+            if games == 0 {
+                0.0
+            } else {
+                wins as f64 / games as f64
+            }
+        });
+
+        EvaluationStats {
+            games,
+            mean_score: mean(&self.scores),
+            median_score,
+            max_score: sorted_scores.last().copied().unwrap_or_default(),
+            win_rates,
+            mean_game_length: mean(&self.game_lengths),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::board::NUM_TILES;
+    use crate::game::board::Tile::{Empty, Value};
+
+    fn board_with_tile(value: u32, score: u32) -> Board<RealGameRng> {
+        let mut tiles = [Empty; NUM_TILES];
+        tiles[0] = Value(value);
+        Board::new_with_tiles_and_score(tiles, score, RealGameRng::new())
+    }
+
+    #[test]
+    fn stats_are_aggregated() {
+        let mut recorder = EvaluationStatsRecorder::default();
+        recorder.record_game(&board_with_tile(512, 6000), 400);
+        recorder.record_game(&board_with_tile(2048, 21000), 1000);
+        recorder.record_game(&board_with_tile(4096, 50000), 2000);
+        recorder.record_game(&board_with_tile(1024, 12000), 600);
+
+        let stats = recorder.stats();
+
+        assert_eq!(stats.games, 4);
+        assert_eq!(stats.mean_score, 22250.0);
+        assert_eq!(stats.median_score, 16500.0);
+        assert_eq!(stats.max_score, 50000);
+        assert_eq!(stats.win_rates, [0.5, 0.25, 0.0]);
+        assert_eq!(stats.mean_game_length, 1000.0);
+    }
+
+    #[test]
+    fn empty_evaluation_has_zero_stats() {
+        let stats = EvaluationStatsRecorder::default().stats();
+
+        assert_eq!(stats.games, 0);
+        assert_eq!(stats.median_score, 0.0);
+        assert_eq!(stats.win_rates, [0.0; 3]);
+    }
+}
@@ -1,17 +1,80 @@
 use burn::{
-    config::{self, Config},
-    module::Module,
-    nn::{Linear, LinearConfig, Relu},
+    config::Config,
+    module::{Ignored, Initializer, Module},
+    nn::{
+        Embedding, EmbeddingConfig, Linear, LinearConfig, PaddingConfig2d, Relu,
+        conv::{Conv2d, Conv2dConfig},
+    },
     prelude::Backend,
-    tensor::Float,
-    Tensor,
+    serde::{Deserialize, Serialize},
+    tensor::{Float, Int, Tensor},
 };
 
-use crate::dqn::model::Model;
+use crate::{
+    dqn::{model::Model, state::StateType},
+    game::{
+        board::{Board, NUM_TILES},
+        game_rng::RealGameRng,
+    },
+};
+
+// Bumped whenever the saved weights change their layout. Version 1 are the models saved before
+// the config was stored with them.
+pub(crate) const MODEL_VERSION: u32 = 2;
+
+const BOARD_SIZE: usize = 4;
+
+// Rows, columns and 2x2 squares, as tile indices
+const TUPLES: [[usize; 4]; 17] = [
+    [0, 1, 2, 3],
+    [4, 5, 6, 7],
+    [8, 9, 10, 11],
+    [12, 13, 14, 15],
+    [0, 4, 8, 12],
+    [1, 5, 9, 13],
+    [2, 6, 10, 14],
+    [3, 7, 11, 15],
+    [0, 1, 4, 5],
+    [1, 2, 5, 6],
+    [2, 3, 6, 7],
+    [4, 5, 8, 9],
+    [5, 6, 9, 10],
+    [6, 7, 10, 11],
+    [8, 9, 12, 13],
+    [9, 10, 13, 14],
+    [10, 11, 14, 15],
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "burn::serde", rename_all = "lowercase")]
+pub(crate) enum ModelArchitecture {
+    // Two fully connected layers over the one-hot features
+    Mlp,
+    // Two 3x3 convolutions over the board, with the tile features as channels
+    Conv,
+    // A 3x3 convolution followed by a tower of residual blocks
+    Residual,
+    // A lookup table per row, column and 2x2 square, indexed by the tiles in it
+    NTuple,
+}
+
+// How boards are turned into the model's inputs, see board_state.rs
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "burn::serde", rename_all = "snake_case")]
+pub(crate) enum FeatureEncoding {
+    // One input per tile and exponent, with exponents from 11 up sharing the last one
+    OneHotExponents,
+}
 
 #[derive(Config, Debug)]
 pub(crate) struct GameModelConfig {
-    // #[config(default = "16")]
+    #[config(default = "MODEL_VERSION")]
+    pub version: u32,
+    #[config(default = "ModelArchitecture::Mlp")]
+    pub architecture: ModelArchitecture,
+    #[config(default = "FeatureEncoding::OneHotExponents")]
+    pub feature_encoding: FeatureEncoding,
+    // The convolutional and n-tuple models read these as NUM_TILES one-hot encoded tiles
     #[config(default = "16 * 12")]
     num_inputs: usize,
     #[config(default = "512")]
@@ -20,23 +83,179 @@ pub(crate) struct GameModelConfig {
     hidden2_size: usize,
     #[config(default = "4")]
     num_outputs: usize,
+    #[config(default = "64")]
+    num_channels: usize,
+    #[config(default = "3")]
+    num_residual_blocks: usize,
 }
 
 impl GameModelConfig {
     pub(crate) fn init<B: Backend>(&self, device: &B::Device) -> GameModel<B> {
+        let network = match self.architecture {
+            ModelArchitecture::Mlp => GameNetwork::Mlp(self.init_mlp(device)),
+            ModelArchitecture::Conv => GameNetwork::Conv(self.init_conv(device)),
+            ModelArchitecture::Residual => GameNetwork::Residual(self.init_residual(device)),
+            ModelArchitecture::NTuple => GameNetwork::NTuple(self.init_n_tuple(device)),
+        };
+
         GameModel {
+            network,
+            config: Ignored(self.clone()),
+        }
+    }
+
+    // Problems that keep a saved model from being used with the current boards and layout
+    pub(crate) fn incompatibilities(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.version != MODEL_VERSION {
+            problems.push(format!(
+                "model version {} isn't supported, expected {}",
+                self.version, MODEL_VERSION
+            ));
+        }
+        let num_features = Board::<RealGameRng>::NUM_FEATURES;
+        if self.num_inputs != num_features {
+            problems.push(format!(
+                "the model has {} inputs, boards have {} features",
+                self.num_inputs, num_features
+            ));
+        }
+        let num_actions = Board::<RealGameRng>::NUM_ACTIONS;
+        if self.num_outputs != num_actions {
+            problems.push(format!(
+                "the model has {} outputs, there are {} moves",
+                self.num_outputs, num_actions
+            ));
+        }
+        problems
+    }
+
+    pub(crate) fn init_legacy<B: Backend>(&self, device: &B::Device) -> LegacyGameModel<B> {
+        let MlpNetwork {
+            hidden1,
+            relu1,
+            hidden2,
+            relu2,
+            head,
+        } = self.init_mlp(device);
+
+        LegacyGameModel {
+            hidden1,
+            relu1,
+            hidden2,
+            relu2,
+            value_output: head.value_output,
+            advantage_output: head.advantage_output,
+        }
+    }
+
+    fn features_per_tile(&self) -> usize {
+        self.num_inputs / NUM_TILES
+    }
+
+    fn init_head<B: Backend>(&self, device: &B::Device) -> DuelingHead<B> {
+        DuelingHead {
+            value_output: LinearConfig::new(self.hidden2_size, 1).init(device),
+            advantage_output: LinearConfig::new(self.hidden2_size, self.num_outputs).init(device),
+        }
+    }
+
+    fn init_conv_layer<B: Backend>(&self, num_in_channels: usize, device: &B::Device) -> Conv2d<B> {
+        Conv2dConfig::new([num_in_channels, self.num_channels], [3, 3])
+            .with_padding(PaddingConfig2d::Same)
+            .init(device)
+    }
+
+    fn init_board_output<B: Backend>(&self, device: &B::Device) -> Linear<B> {
+        LinearConfig::new(self.num_channels * NUM_TILES, self.hidden2_size).init(device)
+    }
+
+    fn init_mlp<B: Backend>(&self, device: &B::Device) -> MlpNetwork<B> {
+        MlpNetwork {
             hidden1: LinearConfig::new(self.num_inputs, self.hidden1_size).init(device),
             relu1: Relu::new(),
             hidden2: LinearConfig::new(self.hidden1_size, self.hidden2_size).init(device),
             relu2: Relu::new(),
-            value_output: LinearConfig::new(self.hidden2_size, 1).init(device),
-            advantage_output: LinearConfig::new(self.hidden2_size, self.num_outputs).init(device),
+            head: self.init_head(device),
+        }
+    }
+
+    fn init_conv<B: Backend>(&self, device: &B::Device) -> ConvNetwork<B> {
+        ConvNetwork {
+            conv1: self.init_conv_layer(self.features_per_tile(), device),
+            conv2: self.init_conv_layer(self.num_channels, device),
+            hidden: self.init_board_output(device),
+            relu: Relu::new(),
+            head: self.init_head(device),
+        }
+    }
+
+    fn init_residual<B: Backend>(&self, device: &B::Device) -> ResidualNetwork<B> {
+        ResidualNetwork {
+            stem: self.init_conv_layer(self.features_per_tile(), device),
+            blocks: (0..self.num_residual_blocks)
+                .map(|_| ResidualBlock {
+                    conv1: self.init_conv_layer(self.num_channels, device),
+                    conv2: self.init_conv_layer(self.num_channels, device),
+                    relu: Relu::new(),
+                })
+                .collect(),
+            hidden: self.init_board_output(device),
+            relu: Relu::new(),
+            head: self.init_head(device),
+        }
+    }
+
+    fn init_n_tuple<B: Backend>(&self, device: &B::Device) -> NTupleNetwork<B> {
+        let num_entries = self.features_per_tile().pow(TUPLES[0].len() as u32);
+        NTupleNetwork {
+            // The state value followed by the advantages. Starting from zero keeps the sums
+            // over all tuples small.
+            tables: EmbeddingConfig::new(TUPLES.len() * num_entries, 1 + self.num_outputs)
+                .with_initializer(Initializer::Zeros)
+                .init(device),
+            features_per_tile: self.features_per_tile(),
         }
     }
 }
 
+// Any of the architectures, along with the config it was built from so that it can be saved
+// and rebuilt
 #[derive(Module, Debug)]
 pub(crate) struct GameModel<B: Backend> {
+    network: GameNetwork<B>,
+    config: Ignored<GameModelConfig>,
+}
+
+impl<B: Backend> GameModel<B> {
+    pub(crate) fn config(&self) -> &GameModelConfig {
+        &self.config
+    }
+}
+
+impl<B: Backend> Model<B> for GameModel<B> {
+    fn forward(&self, input: Tensor<B, 2, Float>) -> Tensor<B, 2, Float> {
+        match &self.network {
+            GameNetwork::Mlp(network) => network.head.forward(network.hidden(input)),
+            GameNetwork::Conv(network) => network.head.forward(network.hidden(input)),
+            GameNetwork::Residual(network) => network.head.forward(network.hidden(input)),
+            GameNetwork::NTuple(network) => network.forward(input),
+        }
+    }
+
+    fn state_values(&self, input: Tensor<B, 2, Float>) -> Tensor<B, 2, Float> {
+        match &self.network {
+            GameNetwork::Mlp(network) => network.head.state_values(network.hidden(input)),
+            GameNetwork::Conv(network) => network.head.state_values(network.hidden(input)),
+            GameNetwork::Residual(network) => network.head.state_values(network.hidden(input)),
+            GameNetwork::NTuple(network) => network.state_values(input),
+        }
+    }
+}
+
+// The layout of version 1 models, which were all built from the default config
+#[derive(Module, Debug)]
+pub(crate) struct LegacyGameModel<B: Backend> {
     hidden1: Linear<B>,
     relu1: Relu,
     hidden2: Linear<B>,
@@ -45,18 +264,229 @@ pub(crate) struct GameModel<B: Backend> {
     advantage_output: Linear<B>,
 }
 
-impl<B: Backend> Model<B> for GameModel<B> {
-    fn forward(&self, input: Tensor<B, 2, Float>) -> Tensor<B, 2, Float> {
+impl<B: Backend> From<LegacyGameModel<B>> for GameModel<B> {
+    fn from(model: LegacyGameModel<B>) -> Self {
+        GameModel {
+            network: GameNetwork::Mlp(MlpNetwork {
+                hidden1: model.hidden1,
+                relu1: model.relu1,
+                hidden2: model.hidden2,
+                relu2: model.relu2,
+                head: DuelingHead {
+                    value_output: model.value_output,
+                    advantage_output: model.advantage_output,
+                },
+            }),
+            config: Ignored(GameModelConfig::new()),
+        }
+    }
+}
+
+#[derive(Module, Debug)]
+enum GameNetwork<B: Backend> {
+    Mlp(MlpNetwork<B>),
+    Conv(ConvNetwork<B>),
+    Residual(ResidualNetwork<B>),
+    NTuple(NTupleNetwork<B>),
+}
+
+#[derive(Module, Debug)]
+struct DuelingHead<B: Backend> {
+    value_output: Linear<B>,
+    advantage_output: Linear<B>,
+}
+
+impl<B: Backend> DuelingHead<B> {
+    fn forward(&self, x: Tensor<B, 2, Float>) -> Tensor<B, 2, Float> {
+        let state_values = self.value_output.forward(x.clone());
+        let advantage_values = self.advantage_output.forward(x);
+
+        dueling_q_values(state_values, advantage_values)
+    }
+
+    fn state_values(&self, x: Tensor<B, 2, Float>) -> Tensor<B, 2, Float> {
+        self.value_output.forward(x)
+    }
+}
+
+fn dueling_q_values<B: Backend>(
+    state_values: Tensor<B, 2, Float>,
+    advantage_values: Tensor<B, 2, Float>,
+) -> Tensor<B, 2, Float> {
+    let mean_advantage = advantage_values.clone().mean_dim(1);
+
+    state_values + advantage_values - mean_advantage
+}
+
+// [batch_size, NUM_TILES * features] to [batch_size, features, BOARD_SIZE, BOARD_SIZE]
+fn board_channels<B: Backend>(input: Tensor<B, 2, Float>) -> Tensor<B, 4, Float> {
+    let [batch_size, num_inputs] = input.dims();
+    input
+        .reshape([batch_size, BOARD_SIZE, BOARD_SIZE, num_inputs / NUM_TILES])
+        .permute([0, 3, 1, 2])
+}
+
+#[derive(Module, Debug)]
+struct MlpNetwork<B: Backend> {
+    hidden1: Linear<B>,
+    relu1: Relu,
+    hidden2: Linear<B>,
+    relu2: Relu,
+    head: DuelingHead<B>,
+}
+
+impl<B: Backend> MlpNetwork<B> {
+    fn hidden(&self, input: Tensor<B, 2, Float>) -> Tensor<B, 2, Float> {
         let x = self.hidden1.forward(input);
         let x = self.relu1.forward(x);
         let x = self.hidden2.forward(x);
-        let x = self.relu2.forward(x);
+        self.relu2.forward(x)
+    }
+}
 
-        let state_values = self.value_output.forward(x.clone());
-        let advantage_values = self.advantage_output.forward(x);
+#[derive(Module, Debug)]
+struct ConvNetwork<B: Backend> {
+    conv1: Conv2d<B>,
+    conv2: Conv2d<B>,
+    hidden: Linear<B>,
+    relu: Relu,
+    head: DuelingHead<B>,
+}
 
-        let mean_advantage = advantage_values.clone().mean_dim(1);
+impl<B: Backend> ConvNetwork<B> {
+    fn hidden(&self, input: Tensor<B, 2, Float>) -> Tensor<B, 2, Float> {
+        let x = self.relu.forward(self.conv1.forward(board_channels(input)));
+        let x = self.relu.forward(self.conv2.forward(x));
+        self.relu.forward(self.hidden.forward(x.flatten(1, 3)))
+    }
+}
+
+#[derive(Module, Debug)]
+struct ResidualBlock<B: Backend> {
+    conv1: Conv2d<B>,
+    conv2: Conv2d<B>,
+    relu: Relu,
+}
+
+impl<B: Backend> ResidualBlock<B> {
+    fn forward(&self, input: Tensor<B, 4, Float>) -> Tensor<B, 4, Float> {
+        let x = self.relu.forward(self.conv1.forward(input.clone()));
+        let x = self.conv2.forward(x);
+        self.relu.forward(x + input)
+    }
+}
+
+#[derive(Module, Debug)]
+struct ResidualNetwork<B: Backend> {
+    stem: Conv2d<B>,
+    blocks: Vec<ResidualBlock<B>>,
+    hidden: Linear<B>,
+    relu: Relu,
+    head: DuelingHead<B>,
+}
+
+impl<B: Backend> ResidualNetwork<B> {
+    fn hidden(&self, input: Tensor<B, 2, Float>) -> Tensor<B, 2, Float> {
+        let x = self.relu.forward(self.stem.forward(board_channels(input)));
+        let x = self.blocks.iter().fold(x, |x, block| block.forward(x));
+        self.relu.forward(self.hidden.forward(x.flatten(1, 3)))
+    }
+}
+
+#[derive(Module, Debug)]
+struct NTupleNetwork<B: Backend> {
+    tables: Embedding<B>,
+    features_per_tile: usize,
+}
 
-        state_values + advantage_values - mean_advantage
+impl<B: Backend> NTupleNetwork<B> {
+    fn forward(&self, input: Tensor<B, 2, Float>) -> Tensor<B, 2, Float> {
+        let outputs = self.outputs(input);
+        let [batch_size, num_outputs] = outputs.dims();
+        let state_values = outputs.clone().slice([0..batch_size, 0..1]);
+        let advantage_values = outputs.slice([0..batch_size, 1..num_outputs]);
+
+        dueling_q_values(state_values, advantage_values)
+    }
+
+    fn state_values(&self, input: Tensor<B, 2, Float>) -> Tensor<B, 2, Float> {
+        let outputs = self.outputs(input);
+        let batch_size = outputs.dims()[0];
+        outputs.slice([0..batch_size, 0..1])
+    }
+
+    // Sum of the table entries of every tuple, shaped [batch_size, 1 + num_outputs]
+    fn outputs(&self, input: Tensor<B, 2, Float>) -> Tensor<B, 2, Float> {
+        let device = input.device();
+        let [batch_size, _] = input.dims();
+        let features_per_tile = self.features_per_tile;
+        let num_entries = features_per_tile.pow(TUPLES[0].len() as u32);
+
+        // The one-hot features back to one index per tile, 0 for empty tiles
+        let feature_indices =
+            Tensor::<B, 1, Int>::arange(0..features_per_tile as i64, &device).float();
+        let tile_indices = input
+            .reshape([batch_size, NUM_TILES, features_per_tile])
+            .mul(feature_indices.reshape([1, 1, features_per_tile]))
+            .sum_dim(2)
+            .reshape([batch_size, NUM_TILES]);
+
+        let tuple_tiles: Vec<i64> = TUPLES.iter().flatten().map(|tile| *tile as i64).collect();
+        let tuple_tiles = Tensor::<B, 1, Int>::from_ints(tuple_tiles.as_slice(), &device);
+        let place_values: Vec<f32> = (0..TUPLES[0].len())
+            .map(|place| features_per_tile.pow(place as u32) as f32)
+            .collect();
+        let place_values = Tensor::<B, 1>::from_floats(place_values.as_slice(), &device);
+        let table_offsets: Vec<i64> = (0..TUPLES.len())
+            .map(|tuple| (tuple * num_entries) as i64)
+            .collect();
+        let table_offsets = Tensor::<B, 1, Int>::from_ints(table_offsets.as_slice(), &device);
+
+        let entries = tile_indices
+            .select(1, tuple_tiles)
+            .reshape([batch_size, TUPLES.len(), TUPLES[0].len()])
+            .mul(place_values.reshape([1, 1, TUPLES[0].len()]))
+            .sum_dim(2)
+            .reshape([batch_size, TUPLES.len()])
+            .int()
+            .add(table_offsets.reshape([1, TUPLES.len()]));
+
+        let outputs = self.tables.forward(entries).sum_dim(1);
+        let num_outputs = outputs.dims()[2];
+        outputs.reshape([batch_size, num_outputs])
+    }
+}
+
+// Needs a backend that runs anywhere
+#[cfg(all(test, feature = "ndarray"))]
+mod tests {
+    use super::*;
+    use crate::{
+        dqn::state::StateType,
+        game::{board::Board, game_rng::RealGameRng},
+    };
+    use burn::backend::NdArray;
+
+    #[test]
+    fn every_architecture_maps_boards_to_q_values() {
+        let device = Default::default();
+        let board = Board::<RealGameRng>::new_with_seed(1);
+        let input = Tensor::<NdArray, 1>::from_floats(board.as_features().as_slice(), &device)
+            .reshape([1, Board::<RealGameRng>::NUM_FEATURES])
+            .repeat_dim(0, 2);
+
+        for architecture in [
+            ModelArchitecture::Mlp,
+            ModelArchitecture::Conv,
+            ModelArchitecture::Residual,
+            ModelArchitecture::NTuple,
+        ] {
+            let model = GameModelConfig::new()
+                .with_architecture(architecture)
+                .init::<NdArray>(&device);
+            assert_eq!(model.forward(input.clone()).dims(), [2, 4]);
+            assert_eq!(model.state_values(input.clone()).dims(), [2, 1]);
+            assert_eq!(model.config().architecture, architecture);
+        }
     }
 }
@@ -0,0 +1,55 @@
+use burn::{prelude::Backend, tensor::backend::AutodiffBackend};
+
+use crate::{
+    dqn::{
+        evaluator::{EvaluationConfig, Evaluator},
+        hyperparameters::Hyperparameters,
+        trainer::Trainer,
+    },
+    game::{board::Board, game_rng::RealGameRng},
+    training::{
+        episode_archive::EpisodeArchive,
+        game_model::{GameModel, GameModelConfig},
+        training_critic::TrainingCritic,
+        training_data_augmenter::TrainingDataAugmenter,
+        training_stats_recorder::TrainingStatsRecorder,
+    },
+};
+
+pub(crate) type GameTrainer<B> = Trainer<
+    B,
+    GameModel<B>,
+    Board<RealGameRng>,
+    TrainingCritic,
+    TrainingStatsRecorder,
+    TrainingDataAugmenter,
+    EpisodeArchive,
+>;
+
+pub(crate) fn new_game_trainer<B: AutodiffBackend>(
+    hyperparameters: Hyperparameters,
+    episode_archive: EpisodeArchive,
+) -> GameTrainer<B> {
+    Trainer::new(
+        hyperparameters.clone(),
+        TrainingCritic::new(&hyperparameters),
+        TrainingDataAugmenter::default(),
+        episode_archive,
+        Default::default(),
+    )
+}
+
+pub(crate) fn new_game_model<B: Backend>(
+    hyperparameters: &Hyperparameters,
+    device: &B::Device,
+) -> GameModel<B> {
+    GameModelConfig::new()
+        .with_architecture(hyperparameters.model_architecture)
+        .init(device)
+}
+
+pub(crate) type GameEvaluator<B> = Evaluator<B, GameModel<B>, Board<RealGameRng>>;
+
+pub(crate) fn new_game_evaluator<B: Backend>(config: EvaluationConfig) -> GameEvaluator<B> {
+    Evaluator::new(config, Default::default())
+}
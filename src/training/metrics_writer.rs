@@ -0,0 +1,243 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use crate::training::tensorboard::{self, EventWriter};
+use crate::training::training_stats_recorder::TrainingStats;
+
+pub(crate) const CSV_FILE_NAME: &str = "metrics.csv";
+pub(crate) const JSONL_FILE_NAME: &str = "metrics.jsonl";
+
+// Appends the stats of every epoch to a run directory, as CSV, as JSON Lines with the full
+// stats and optionally as TensorBoard scalars. Existing files are continued, e.g. after resuming,
+// unless the writer replaces them.
+pub(crate) struct MetricsWriter {
+    csv_file: File,
+    // Read from the existing file or taken from the first written epoch
+    csv_columns: Option<Vec<String>>,
+    jsonl_file: File,
+    event_writer: Option<EventWriter>,
+}
+
+impl MetricsWriter {
+    pub(crate) fn new(directory: PathBuf, with_tensorboard: bool) -> io::Result<MetricsWriter> {
+        fs::create_dir_all(&directory)?;
+        let csv_path = directory.join(CSV_FILE_NAME);
+        let csv_columns = read_csv_columns(&csv_path)?;
+        let event_writer = if with_tensorboard {
+            Some(EventWriter::new(&directory)?)
+        } else {
+            None
+        };
+
+        Ok(MetricsWriter {
+            csv_file: append(&csv_path)?,
+            csv_columns,
+            jsonl_file: append(&directory.join(JSONL_FILE_NAME))?,
+            event_writer,
+        })
+    }
+
+    // For writing a whole history again, which would otherwise repeat the epochs already written
+    pub(crate) fn replacing(
+        directory: PathBuf,
+        with_tensorboard: bool,
+    ) -> io::Result<MetricsWriter> {
+        if directory.exists() {
+            for file_name in [CSV_FILE_NAME, JSONL_FILE_NAME] {
+                let file_path = directory.join(file_name);
+                if file_path.exists() {
+                    fs::remove_file(file_path)?;
+                }
+            }
+            if with_tensorboard {
+                tensorboard::remove_event_files(&directory)?;
+            }
+        }
+
+        MetricsWriter::new(directory, with_tensorboard)
+    }
+
+    pub(crate) fn write(&mut self, stats: &TrainingStats) -> io::Result<()> {
+        let metrics = metrics(stats);
+
+        let columns = match &self.csv_columns {
+            Some(columns) => columns,
+            None => {
+                let columns: Vec<String> = ["epoch", "game_seed"]
+                    .into_iter()
+                    .map(String::from)
+                    .chain(metrics.iter().map(|(name, _)| name.clone()))
+                    .collect();
+                self.csv_file
+                    .write_all(format!("{}\n", columns.join(",")).as_bytes())?;
+                self.csv_columns.insert(columns)
+            }
+        };
+        let row: Vec<String> = columns
+            .iter()
+            .map(|column| match column.as_str() {
+                "epoch" => stats.epochs.to_string(),
+                "game_seed" => stats.game_seed.to_string(),
+                _ => metrics
+                    .iter()
+                    .find(|(name, _)| name == column)
+                    .and_then(|(_, value)| *value)
+                    .map(|value| value.to_string())
+                    .unwrap_or_default(),
+            })
+            .collect();
+        self.csv_file
+            .write_all(format!("{}\n", row.join(",")).as_bytes())?;
+
+        let json = serde_json::to_string(stats).map_err(io::Error::other)?;
+        self.jsonl_file
+            .write_all(format!("{}\n", json).as_bytes())?;
+
+        if let Some(event_writer) = &mut self.event_writer {
+            let scalars: Vec<(String, f32)> = metrics
+                .into_iter()
+                .filter_map(|(name, value)| value.map(|value| (name, value)))
+                .collect();
+            event_writer.write_scalars(stats.epochs, &scalars)?;
+        }
+
+        Ok(())
+    }
+}
+
+// Every number of the stats besides the epoch and the seed, missing values are empty
+fn metrics(stats: &TrainingStats) -> Vec<(String, Option<f32>)> {
+    let mut metrics = vec![
+        ("score".to_string(), Some(stats.last_epoch_score as f32)),
+        (
+            "game_length".to_string(),
+            Some(stats.last_epoch_length as f32),
+        ),
+        ("best_tile".to_string(), Some(stats.best_tile as f32)),
+        ("reward".to_string(), Some(stats.cumulated_epoch_rewards)),
+        ("epsilon".to_string(), Some(stats.epsilon as f32)),
+        (
+            "replay_buffer_size".to_string(),
+            Some(stats.replay_buffer_size as f32),
+        ),
+        (
+            "training_steps".to_string(),
+            Some(stats.training_steps as f32),
+        ),
+        ("loss".to_string(), stats.mean_loss),
+        ("mean_q_value".to_string(), stats.mean_q_value),
+        ("max_q_value".to_string(), stats.max_q_value),
+        ("td_error".to_string(), stats.mean_td_error),
+        ("gradient_norm".to_string(), stats.gradient_norm),
+        ("illegal_greedy_rate".to_string(), stats.illegal_greedy_rate),
+    ];
+    for (name, value) in &stats.reward_terms {
+        metrics.push((format!("reward_{}", name), Some(*value)));
+    }
+    metrics
+}
+
+fn read_csv_columns(file_path: &Path) -> io::Result<Option<Vec<String>>> {
+    if !file_path.exists() {
+        return Ok(None);
+    }
+    let mut header = String::new();
+    BufReader::new(File::open(file_path)?).read_line(&mut header)?;
+    let header = header.trim_end();
+    if header.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(header.split(',').map(String::from).collect()))
+}
+
+fn append(file_path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(file_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(epochs: usize, mean_loss: Option<f32>) -> TrainingStats {
+        TrainingStats {
+            epochs,
+            game_seed: 7,
+            cumulated_epoch_rewards: 1.5,
+            last_epoch_score: 1024,
+            last_epoch_length: 100,
+            best_tile: 128,
+            replay_buffer_size: 100,
+            epsilon: 0.5,
+            reward_terms: vec![("empty_tiles".to_string(), 1.5)],
+            training_steps: mean_loss.map_or(0, |_| 1),
+            mean_loss,
+            mean_q_value: mean_loss,
+            max_q_value: mean_loss,
+            mean_td_error: mean_loss,
+            gradient_norm: mean_loss,
+            illegal_greedy_rate: None,
+        }
+    }
+
+    fn test_directory(name: &str) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("dqn2048-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&directory);
+        directory
+    }
+
+    #[test]
+    fn epochs_are_appended_across_writers() {
+        let directory = test_directory("epochs_are_appended_across_writers");
+
+        MetricsWriter::new(directory.clone(), false)
+            .unwrap()
+            .write(&stats(1, None))
+            .unwrap();
+        MetricsWriter::new(directory.clone(), false)
+            .unwrap()
+            .write(&stats(2, Some(0.25)))
+            .unwrap();
+
+        let csv = fs::read_to_string(directory.join(CSV_FILE_NAME)).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("epoch,game_seed,score,"));
+        assert!(lines[0].ends_with(",reward_empty_tiles"));
+        assert!(lines[1].starts_with("1,7,1024,100,128,1.5,"));
+        assert!(lines[1].contains(",0,,,,,,,1.5"));
+        assert!(lines[2].contains(",1,0.25,0.25,"));
+
+        let jsonl = fs::read_to_string(directory.join(JSONL_FILE_NAME)).unwrap();
+        let restored: Vec<TrainingStats> = jsonl
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(restored[1].mean_loss, Some(0.25));
+    }
+
+    #[test]
+    fn replacing_writers_start_over() {
+        let directory = test_directory("replacing_writers_start_over");
+        for _ in 0..2 {
+            let mut writer = MetricsWriter::replacing(directory.clone(), true).unwrap();
+            writer.write(&stats(1, None)).unwrap();
+            writer.write(&stats(2, Some(0.25))).unwrap();
+        }
+
+        let csv = fs::read_to_string(directory.join(CSV_FILE_NAME)).unwrap();
+        assert_eq!(csv.lines().count(), 3);
+        let jsonl = fs::read_to_string(directory.join(JSONL_FILE_NAME)).unwrap();
+        assert_eq!(jsonl.lines().count(), 2);
+        let num_event_files = fs::read_dir(&directory)
+            .unwrap()
+            .filter(|entry| {
+                let file_name = entry.as_ref().unwrap().file_name();
+                file_name.to_string_lossy().contains("tfevents")
+            })
+            .count();
+        assert_eq!(num_event_files, 1);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -1,5 +1,12 @@
 pub(crate) mod board_state;
+pub(crate) mod episode_archive;
+pub(crate) mod evaluation_stats_recorder;
 pub(crate) mod game_model;
+pub(crate) mod game_trainer;
+pub(crate) mod metrics_writer;
+pub(crate) mod model_storage;
+pub(crate) mod reward_terms;
+pub(crate) mod tensorboard;
 pub(crate) mod training_critic;
 pub(crate) mod training_stats_recorder;
 pub(crate) mod training_thread;
@@ -0,0 +1,253 @@
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use burn::{
+    config::{Config, ConfigError},
+    module::Module,
+    prelude::Backend,
+    record::{
+        BinBytesRecorder, DefaultFileRecorder, FullPrecisionSettings, Recorder, RecorderError,
+    },
+    tensor::backend::AutodiffBackend,
+};
+
+use crate::dqn::checkpoint::{CheckpointError, HYPERPARAMETERS_FILE_NAME};
+use crate::dqn::hyperparameters::{Hyperparameters, HyperparametersError};
+use crate::training::{
+    game_model::{GameModel, GameModelConfig},
+    game_trainer::GameTrainer,
+    training_stats_recorder::TrainingStats,
+};
+
+const MODEL_FILE_NAME: &str = "model";
+const MODEL_CONFIG_EXTENSION: &str = "config.json";
+const STATS_HISTORY_FILE_NAME: &str = "stats_history.json";
+
+#[derive(Config, Debug)]
+struct StatsHistory {
+    epochs: Vec<TrainingStats>,
+}
+
+// The model's config is saved next to its weights, e.g. model.config.json for model.mpk, so that
+// loading rebuilds the same architecture
+pub(crate) fn save_model<B: Backend>(
+    model: &GameModel<B>,
+    file_path: PathBuf,
+) -> Result<(), ModelError> {
+    let recorder = DefaultFileRecorder::<FullPrecisionSettings>::new();
+
+    model.config().save(model_config_path(&file_path))?;
+    model.clone().save_file(file_path, &recorder)?;
+    Ok(())
+}
+
+// Files without a config are version 1 models and are converted to the current layout
+pub(crate) fn load_model<B: Backend>(
+    file_path: PathBuf,
+    device: &B::Device,
+) -> Result<GameModel<B>, ModelError> {
+    let recorder = DefaultFileRecorder::<FullPrecisionSettings>::new();
+    let config_path = model_config_path(&file_path);
+    if !config_path.exists() {
+        let model = GameModelConfig::new()
+            .init_legacy(device)
+            .load_file(file_path, &recorder, device)?;
+        return Ok(model.into());
+    }
+
+    let config = GameModelConfig::load(&config_path)?;
+    let problems = config.incompatibilities();
+    if !problems.is_empty() {
+        return Err(ModelError::Incompatible(problems));
+    }
+
+    let model = config.init(device);
+    let num_params = model.num_params();
+    let model = model.load_file(file_path, &recorder, device)?;
+    // Weights of another size load without complaint and only fail once the model is used
+    if model.num_params() != num_params {
+        return Err(ModelError::Incompatible(vec![format!(
+            "the weights hold {} parameters, the config describes {}",
+            model.num_params(),
+            num_params
+        )]));
+    }
+
+    Ok(model)
+}
+
+// Accepts model files as well as checkpoint directories
+pub(crate) fn load_model_or_checkpoint<B: Backend>(
+    path: &Path,
+    device: &B::Device,
+) -> Result<GameModel<B>, ModelError> {
+    if path.is_dir() {
+        load_model(path.join(MODEL_FILE_NAME), device)
+    } else {
+        load_model(path.to_path_buf(), device)
+    }
+}
+
+fn model_config_path(file_path: &Path) -> PathBuf {
+    file_path.with_extension(MODEL_CONFIG_EXTENSION)
+}
+
+// Used to hand the model over to another thread, which may run it on another backend
+#[derive(Debug)]
+pub(crate) struct SharedModel {
+    config: GameModelConfig,
+    bytes: Vec<u8>,
+}
+
+pub(crate) fn share_model<B: Backend>(model: &GameModel<B>) -> Result<SharedModel, ModelError> {
+    let recorder = BinBytesRecorder::<FullPrecisionSettings>::default();
+
+    Ok(SharedModel {
+        config: model.config().clone(),
+        bytes: recorder.record(model.clone().into_record(), ())?,
+    })
+}
+
+pub(crate) fn model_from_shared<B: Backend>(
+    shared_model: SharedModel,
+    device: &B::Device,
+) -> Result<GameModel<B>, ModelError> {
+    let recorder = BinBytesRecorder::<FullPrecisionSettings>::default();
+    let record = recorder.load(shared_model.bytes, device)?;
+
+    Ok(shared_model.config.init(device).load_record(record))
+}
+
+#[derive(Debug)]
+pub(crate) enum ModelError {
+    Io(io::Error),
+    Recorder(RecorderError),
+    Config(ConfigError),
+    Incompatible(Vec<String>),
+}
+
+impl Display for ModelError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ModelError::Io(error) => write!(f, "Model IO error: {}", error),
+            ModelError::Recorder(error) => write!(f, "Model record error: {}", error),
+            ModelError::Config(error) => write!(f, "Model config error: {}", error),
+            ModelError::Incompatible(problems) => {
+                write!(f, "Incompatible model: {}", problems.join("; "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for ModelError {}
+
+impl From<io::Error> for ModelError {
+    fn from(error: io::Error) -> Self {
+        ModelError::Io(error)
+    }
+}
+
+impl From<RecorderError> for ModelError {
+    fn from(error: RecorderError) -> Self {
+        ModelError::Recorder(error)
+    }
+}
+
+impl From<ConfigError> for ModelError {
+    fn from(error: ConfigError) -> Self {
+        ModelError::Config(error)
+    }
+}
+
+// A checkpoint is a directory holding the model, the trainer's state and the stats of every
+// epoch so far. The replay buffer isn't saved and refills after resuming, see
+// Trainer::save_checkpoint().
+pub(crate) fn save_checkpoint<B: AutodiffBackend>(
+    directory: &Path,
+    model: &GameModel<B>,
+    trainer: &GameTrainer<B>,
+    stats_history: &[TrainingStats],
+) -> Result<(), CheckpointError> {
+    trainer.save_checkpoint(directory, model)?;
+    save_model(model, directory.join(MODEL_FILE_NAME))?;
+    StatsHistory::new(stats_history.to_vec()).save(directory.join(STATS_HISTORY_FILE_NAME))?;
+
+    Ok(())
+}
+
+pub(crate) fn load_checkpoint<B: AutodiffBackend>(
+    directory: &Path,
+    trainer: &mut GameTrainer<B>,
+    device: &B::Device,
+) -> Result<(GameModel<B>, Vec<TrainingStats>), CheckpointError> {
+    let model = load_model(directory.join(MODEL_FILE_NAME), device)?;
+    let stats_history = StatsHistory::load(directory.join(STATS_HISTORY_FILE_NAME))?;
+    trainer.load_checkpoint(directory, &model)?;
+
+    Ok((model, stats_history.epochs))
+}
+
+// The trainer has to be created with these before calling load_checkpoint(). Checkpoints saved
+// without hyperparameters fall back to the defaults.
+pub(crate) fn load_checkpoint_hyperparameters(
+    directory: &Path,
+) -> Result<Hyperparameters, HyperparametersError> {
+    let file_path = directory.join(HYPERPARAMETERS_FILE_NAME);
+    if file_path.exists() {
+        Hyperparameters::load_file(&file_path)
+    } else {
+        Ok(Hyperparameters::new())
+    }
+}
+
+// Needs a backend that runs anywhere
+#[cfg(all(test, feature = "ndarray"))]
+mod tests {
+    use super::*;
+    use crate::training::game_model::MODEL_VERSION;
+    use burn::backend::NdArray;
+
+    fn test_directory(name: &str) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("dqn2048-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    #[test]
+    fn models_without_config_load_as_legacy_models() {
+        let file_path = test_directory("models-without-config-load-as-legacy-models").join("model");
+        let recorder = DefaultFileRecorder::<FullPrecisionSettings>::new();
+        GameModelConfig::new()
+            .init_legacy::<NdArray>(&Default::default())
+            .save_file(file_path.clone(), &recorder)
+            .unwrap();
+
+        let model = load_model::<NdArray>(file_path.with_extension("mpk"), &Default::default());
+        assert_eq!(model.unwrap().config().version, MODEL_VERSION);
+    }
+
+    #[test]
+    fn weights_not_matching_the_config_are_incompatible() {
+        let file_path =
+            test_directory("weights-not-matching-the-config-are-incompatible").join("model.mpk");
+        let model = GameModelConfig::new().init::<NdArray>(&Default::default());
+        save_model(&model, file_path.clone()).unwrap();
+
+        GameModelConfig::new()
+            .with_hidden1_size(64)
+            .save(model_config_path(&file_path))
+            .unwrap();
+        let result = load_model::<NdArray>(file_path.clone(), &Default::default());
+        assert!(matches!(result, Err(ModelError::Incompatible(_))));
+
+        GameModelConfig::new()
+            .with_num_inputs(16 * 16)
+            .save(model_config_path(&file_path))
+            .unwrap();
+        let result = load_model::<NdArray>(file_path, &Default::default());
+        assert!(matches!(result, Err(ModelError::Incompatible(_))));
+    }
+}
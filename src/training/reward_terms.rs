@@ -0,0 +1,217 @@
+use crate::{
+    dqn::{hyperparameters::Hyperparameters, state::StateType},
+    game::{
+        bit_board::{exponent_at, moved_cells},
+        board::{Board, Direction, NUM_TILES},
+        game_rng::RealGameRng,
+    },
+};
+
+const ROW_LENGTH: usize = 4;
+const CORNERS: [usize; 4] = [0, 3, 12, 15];
+
+// One part of the shaped reward. Values are unweighted, the weights come from the
+// reward_* hyperparameters and terms weighted 0 are skipped.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum RewardTerm {
+    // Points gained by the move
+    ScoreDelta,
+    // log2 of the points gained, 0 without a merge
+    LogScore,
+    // Share of empty tiles after the move
+    EmptyTiles,
+    // Share of neighboring tiles ordered like the rest of their row or column
+    Monotonicity,
+    // Minus the mean exponent difference between neighboring tiles
+    Smoothness,
+    // 1 when the largest tile is in a corner
+    MaxTileInCorner,
+    // Number of merges made by the move, counted before the new tile is placed
+    Merges,
+    // 1 when the move loses the game
+    GameOver,
+}
+
+impl RewardTerm {
+    pub(crate) const ALL: [RewardTerm; 8] = [
+        RewardTerm::ScoreDelta,
+        RewardTerm::LogScore,
+        RewardTerm::EmptyTiles,
+        RewardTerm::Monotonicity,
+        RewardTerm::Smoothness,
+        RewardTerm::MaxTileInCorner,
+        RewardTerm::Merges,
+        RewardTerm::GameOver,
+    ];
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            RewardTerm::ScoreDelta => "score_delta",
+            RewardTerm::LogScore => "log_score",
+            RewardTerm::EmptyTiles => "empty_tiles",
+            RewardTerm::Monotonicity => "monotonicity",
+            RewardTerm::Smoothness => "smoothness",
+            RewardTerm::MaxTileInCorner => "max_tile_in_corner",
+            RewardTerm::Merges => "merges",
+            RewardTerm::GameOver => "game_over",
+        }
+    }
+
+    pub(crate) fn weight(&self, hyperparameters: &Hyperparameters) -> f32 {
+        match self {
+            RewardTerm::ScoreDelta => hyperparameters.reward_score_delta,
+            RewardTerm::LogScore => hyperparameters.reward_log_score,
+            RewardTerm::EmptyTiles => hyperparameters.reward_empty_tiles,
+            RewardTerm::Monotonicity => hyperparameters.reward_monotonicity,
+            RewardTerm::Smoothness => hyperparameters.reward_smoothness,
+            RewardTerm::MaxTileInCorner => hyperparameters.reward_max_tile_in_corner,
+            RewardTerm::Merges => hyperparameters.reward_merges,
+            RewardTerm::GameOver => hyperparameters.reward_game_over,
+        }
+    }
+
+    pub(crate) fn value(
+        &self,
+        state: &Board<RealGameRng>,
+        direction: &Direction,
+        next_state: &Board<RealGameRng>,
+    ) -> f32 {
+        let score_gain = next_state.score.saturating_sub(state.score) as f32;
+        let next_exponents = exponents(next_state.pack());
+
+        match self {
+            RewardTerm::ScoreDelta => score_gain,
+            RewardTerm::LogScore => {
+                if score_gain > 0.0 {
+                    score_gain.log2()
+                } else {
+                    0.0
+                }
+            }
+            RewardTerm::EmptyTiles => {
+                next_exponents
+                    .iter()
+                    .filter(|exponent| **exponent == 0)
+                    .count() as f32
+                    / NUM_TILES as f32
+            }
+            RewardTerm::Monotonicity => monotonicity(&next_exponents),
+            RewardTerm::Smoothness => smoothness(&next_exponents),
+            RewardTerm::MaxTileInCorner => {
+                let max_exponent = next_exponents.iter().max().copied().unwrap_or_default();
+                let is_in_corner = CORNERS
+                    .iter()
+                    .any(|corner| next_exponents[*corner] == max_exponent);
+                if is_in_corner { 1.0 } else { 0.0 }
+            }
+            RewardTerm::Merges => {
+                // Every merge removes a tile
+                let cells = state.pack();
+                let (moved, _) = moved_cells(cells, *direction);
+                let occupied = |exponents: Vec<u32>| exponents.iter().filter(|e| **e > 0).count();
+                (occupied(exponents(cells)) - occupied(exponents(moved))) as f32
+            }
+            RewardTerm::GameOver => {
+                if next_state.is_over() {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+}
+
+fn exponents(cells: u64) -> Vec<u32> {
+    (0..NUM_TILES)
+        .map(|index| exponent_at(cells, index) as u32)
+        .collect()
+}
+
+// Rows followed by columns, each as tile indices
+fn lines() -> impl Iterator<Item = [usize; ROW_LENGTH]> {
+    let rows = (0..ROW_LENGTH).map(|row| [0, 1, 2, 3].map(|column| row * ROW_LENGTH + column));
+    let columns = (0..ROW_LENGTH).map(|column| [0, 1, 2, 3].map(|row| row * ROW_LENGTH + column));
+    rows.chain(columns)
+}
+
+fn monotonicity(exponents: &[u32]) -> f32 {
+    let num_lines = 2 * ROW_LENGTH;
+    let ordered_pairs: usize = lines()
+        .map(|line| {
+            let pairs = line
+                .windows(2)
+                .map(|pair| (exponents[pair[0]], exponents[pair[1]]));
+            let increasing = pairs.clone().filter(|(lhs, rhs)| lhs <= rhs).count();
+            let decreasing = pairs.filter(|(lhs, rhs)| lhs >= rhs).count();
+            increasing.max(decreasing)
+        })
+        .sum();
+
+    ordered_pairs as f32 / (num_lines * (ROW_LENGTH - 1)) as f32
+}
+
+fn smoothness(exponents: &[u32]) -> f32 {
+    let differences: Vec<u32> = lines()
+        .flat_map(|line| {
+            line.windows(2)
+                .map(|pair| (exponents[pair[0]], exponents[pair[1]]))
+                .filter(|(lhs, rhs)| *lhs > 0 && *rhs > 0)
+                .map(|(lhs, rhs)| lhs.abs_diff(rhs))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    if differences.is_empty() {
+        0.0
+    } else {
+        -(differences.iter().sum::<u32>() as f32) / differences.len() as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::board::{Direction, Tile::Empty, Tile::Value};
+
+    #[test]
+    fn terms_describe_the_move() {
+        let mut tiles = [Empty; NUM_TILES];
+        // 2 2 4 4 in the first row, which becomes . . 4 8 and a new tile
+        tiles[0] = Value(2);
+        tiles[1] = Value(2);
+        tiles[2] = Value(4);
+        tiles[3] = Value(4);
+        let state = Board::new_with_tiles(tiles, RealGameRng::new_with_seed(0));
+        let next_state = state.advance(&Direction::Right);
+
+        let value = |term: RewardTerm| term.value(&state, &Direction::Right, &next_state);
+
+        assert_eq!(value(RewardTerm::ScoreDelta), 12.0);
+        assert_eq!(value(RewardTerm::Merges), 2.0);
+        assert_eq!(value(RewardTerm::MaxTileInCorner), 1.0);
+        assert_eq!(value(RewardTerm::GameOver), 0.0);
+        assert!(value(RewardTerm::Monotonicity) > 0.5);
+    }
+
+    #[test]
+    fn merges_dont_depend_on_the_new_tile() {
+        let mut tiles = [Empty; NUM_TILES];
+        // 2 2 . . in the first row, with and without a new tile after moving left
+        tiles[0] = Value(2);
+        tiles[1] = Value(2);
+        let state = Board::new_with_tiles(tiles, RealGameRng::new_with_seed(0));
+        let with_new_tile = state.advance(&Direction::Left);
+        tiles[0] = Value(4);
+        tiles[1] = Empty;
+        let without_new_tile =
+            Board::new_with_tiles_and_score(tiles, 4, RealGameRng::new_with_seed(0));
+
+        for next_state in [with_new_tile, without_new_tile] {
+            assert_eq!(
+                RewardTerm::Merges.value(&state, &Direction::Left, &next_state),
+                1.0
+            );
+        }
+    }
+}
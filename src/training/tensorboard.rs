@@ -0,0 +1,207 @@
+use std::fs::{self, File};
+use std::io::{self, ErrorKind, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const FILE_VERSION: &str = "brain.Event:2";
+const FILE_NAME_PREFIX: &str = "events.out.tfevents.";
+const FILE_NAME_SUFFIX: &str = ".dqn2048";
+
+// Writes scalars as a TensorBoard event file: TFRecords holding `Event` protobufs, encoded by
+// hand since only a few fields are needed.
+pub(crate) struct EventWriter {
+    file: File,
+}
+
+impl EventWriter {
+    pub(crate) fn new(directory: &Path) -> io::Result<EventWriter> {
+        let mut writer = EventWriter {
+            file: create_event_file(directory)?,
+        };
+
+        let mut event = Vec::new();
+        write_double_field(&mut event, 1, wall_time());
+        write_bytes_field(&mut event, 3, FILE_VERSION.as_bytes());
+        writer.write_record(&event)?;
+        Ok(writer)
+    }
+
+    pub(crate) fn write_scalars(
+        &mut self,
+        step: usize,
+        scalars: &[(String, f32)],
+    ) -> io::Result<()> {
+        let mut summary = Vec::new();
+        for (tag, value) in scalars {
+            let mut summary_value = Vec::new();
+            write_bytes_field(&mut summary_value, 1, tag.as_bytes());
+            write_float_field(&mut summary_value, 2, *value);
+            write_bytes_field(&mut summary, 1, &summary_value);
+        }
+
+        let mut event = Vec::new();
+        write_double_field(&mut event, 1, wall_time());
+        write_varint_field(&mut event, 2, step as u64);
+        write_bytes_field(&mut event, 5, &summary);
+        self.write_record(&event)
+    }
+
+    fn write_record(&mut self, data: &[u8]) -> io::Result<()> {
+        let length = (data.len() as u64).to_le_bytes();
+        let mut record = Vec::with_capacity(data.len() + 16);
+        record.extend_from_slice(&length);
+        record.extend_from_slice(&masked_crc32c(&length).to_le_bytes());
+        record.extend_from_slice(data);
+        record.extend_from_slice(&masked_crc32c(data).to_le_bytes());
+        self.file.write_all(&record)?;
+        self.file.flush()
+    }
+}
+
+// Files made within the same second get a counter, TensorBoard only needs "tfevents" in the name
+fn create_event_file(directory: &Path) -> io::Result<File> {
+    let file_name = format!(
+        "{}{}{}",
+        FILE_NAME_PREFIX,
+        wall_time() as u64,
+        FILE_NAME_SUFFIX
+    );
+    let mut counter = 0;
+    loop {
+        let file_path = match counter {
+            0 => directory.join(&file_name),
+            _ => directory.join(format!("{}.{}", file_name, counter)),
+        };
+        match File::create_new(file_path) {
+            Err(error) if error.kind() == ErrorKind::AlreadyExists => counter += 1,
+            result => return result,
+        }
+    }
+}
+
+// Only the event files written by EventWriter, not the ones of other programs
+pub(crate) fn remove_event_files(directory: &Path) -> io::Result<()> {
+    for entry in fs::read_dir(directory)? {
+        let file_name = entry?.file_name();
+        let file_name = file_name.to_string_lossy();
+        if file_name.starts_with(FILE_NAME_PREFIX) && file_name.contains(FILE_NAME_SUFFIX) {
+            fs::remove_file(directory.join(file_name.as_ref()))?;
+        }
+    }
+    Ok(())
+}
+
+fn wall_time() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
+
+fn write_varint(buffer: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buffer.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+fn write_varint_field(buffer: &mut Vec<u8>, field: u64, value: u64) {
+    write_varint(buffer, field << 3);
+    write_varint(buffer, value);
+}
+
+fn write_double_field(buffer: &mut Vec<u8>, field: u64, value: f64) {
+    write_varint(buffer, (field << 3) | 1);
+    buffer.extend_from_slice(&value.to_le_bytes());
+}
+
+fn write_bytes_field(buffer: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+    write_varint(buffer, (field << 3) | 2);
+    write_varint(buffer, bytes.len() as u64);
+    buffer.extend_from_slice(bytes);
+}
+
+fn write_float_field(buffer: &mut Vec<u8>, field: u64, value: f32) {
+    write_varint(buffer, (field << 3) | 5);
+    buffer.extend_from_slice(&value.to_le_bytes());
+}
+
+fn crc32c(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0x82f6_3b78
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn masked_crc32c(data: &[u8]) -> u32 {
+    let crc = crc32c(data);
+    crc.rotate_right(15).wrapping_add(0xa282_ead8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_are_framed_like_tfrecords() {
+        assert_eq!(crc32c(b"123456789"), 0xe306_9283);
+
+        let directory = std::env::temp_dir().join(format!(
+            "dqn2048-{}-records_are_framed_like_tfrecords",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        let mut writer = EventWriter::new(&directory).unwrap();
+        writer
+            .write_scalars(3, &[("score".to_string(), 1024.0)])
+            .unwrap();
+
+        let file_path = std::fs::read_dir(&directory)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path();
+        let bytes = std::fs::read(file_path).unwrap();
+        let mut offset = 0;
+        let mut num_records = 0;
+        while offset < bytes.len() {
+            let length = u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap()) as usize;
+            let data = &bytes[offset + 12..offset + 12 + length];
+            let data_crc = &bytes[offset + 12 + length..offset + 16 + length];
+            assert_eq!(data_crc, masked_crc32c(data).to_le_bytes());
+            offset += length + 16;
+            num_records += 1;
+        }
+        assert_eq!(num_records, 2);
+    }
+
+    #[test]
+    fn writers_in_the_same_second_get_their_own_files() {
+        let directory = std::env::temp_dir().join(format!(
+            "dqn2048-{}-writers_in_the_same_second_get_their_own_files",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+
+        let _writers: Vec<EventWriter> = (0..3)
+            .map(|_| EventWriter::new(&directory).unwrap())
+            .collect();
+
+        assert_eq!(std::fs::read_dir(&directory).unwrap().count(), 3);
+        remove_event_files(&directory).unwrap();
+        assert_eq!(std::fs::read_dir(&directory).unwrap().count(), 0);
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -1,50 +1,66 @@
 use crate::{
-    dqn::{critic::CriticType, state::StateType},
+    dqn::{critic::CriticType, hyperparameters::Hyperparameters, state::StateType},
     game::{board::Board, game_rng::RealGameRng},
+    training::reward_terms::RewardTerm,
 };
 
-pub(crate) struct TrainingCritic {}
+// Sums the reward terms with a non-zero weight in the hyperparameters
+#[derive(Clone)]
+pub(crate) struct TrainingCritic {
+    weighted_terms: Vec<(RewardTerm, f32)>,
+}
 
 impl TrainingCritic {
-    pub(crate) fn new() -> Self {
-        TrainingCritic {}
+    pub(crate) fn new(hyperparameters: &Hyperparameters) -> Self {
+        let weighted_terms = RewardTerm::ALL
+            .into_iter()
+            .map(|term| (term, term.weight(hyperparameters)))
+            .filter(|(_, weight)| *weight != 0.0)
+            .collect();
+
+        TrainingCritic { weighted_terms }
     }
 }
 
 impl CriticType for TrainingCritic {
     type State = Board<RealGameRng>;
 
-    fn reward(
+    fn reward_terms(
         &self,
         state: &Self::State,
         action: &<Self::State as StateType>::Action,
         next_state: &Self::State,
-    ) -> f32 {
-        if next_state.is_over() {
-            return -10.0;
-        }
-
-        let score_gain = (next_state.score.saturating_sub(state.score)) as f32;
-        let score_reward = if score_gain > 0.0 {
-            score_gain.log2() * 0.1
-        } else { 0.0 };
-
-        let empty_reward = next_state.num_empty_tiles() as f32 / 16.0;
+    ) -> Vec<(&'static str, f32)> {
+        let is_over = next_state.is_over();
 
-        score_reward + empty_reward
+        self.weighted_terms
+            .iter()
+            .filter(|(term, _)| (*term == RewardTerm::GameOver) == is_over)
+            .map(|(term, weight)| (term.name(), weight * term.value(state, action, next_state)))
+            .collect()
     }
 }
 
-impl Board<RealGameRng> {
-    fn num_empty_tiles(&self) -> usize {
-        let mut empty = 0;
-        for row in 0..4 {
-            for column in 0..4 {
-                if matches!(self.value_at(row, column), None) {
-                    empty += 1;
-                }
-            }
-        }
-        empty
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dqn::critic::total_reward;
+    use crate::game::board::{Direction, NUM_TILES, Tile::Empty, Tile::Value};
+
+    #[test]
+    fn default_weights_keep_the_original_reward() {
+        let critic = TrainingCritic::new(&Hyperparameters::new());
+        let mut tiles = [Empty; NUM_TILES];
+        tiles[0] = Value(4);
+        tiles[1] = Value(4);
+        let state = Board::new_with_tiles(tiles, RealGameRng::new_with_seed(0));
+        let next_state = state.advance(&Direction::Left);
+
+        let reward_terms = critic.reward_terms(&state, &Direction::Left, &next_state);
+
+        let names: Vec<&str> = reward_terms.iter().map(|(name, _)| *name).collect();
+        assert_eq!(names, ["log_score", "empty_tiles"]);
+        // log2(8) * 0.1 and 14 empty tiles out of 16
+        assert!((total_reward(&reward_terms) - (0.3 + 14.0 / 16.0)).abs() < 1e-6);
     }
-}
\ No newline at end of file
+}
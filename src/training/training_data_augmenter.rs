@@ -1,10 +1,12 @@
 use crate::dqn::data_augmenter::DataAugmenterType;
 use crate::dqn::replay_buffer::StateTransition;
-use crate::dqn::state::StateType;
-use crate::game::board::Tile::{Empty, Value};
-use crate::game::board::{Board, Direction, Tile, NUM_COLUMNS, NUM_ROWS, NUM_TILES};
-use crate::game::game_rng::{GameRng, RealGameRng};
+use crate::dqn::state::{ActionType, StateType};
+use crate::game::bit_board::transpose;
+use crate::game::board::{Board, Direction};
+use crate::game::game_rng::RealGameRng;
 
+// Adds the 7 rotations and reflections of every transition. They're made from the packed boards,
+// so that no board or game RNG is created for them.
 #[derive(Default)]
 pub(crate) struct TrainingDataAugmenter {}
 
@@ -17,78 +19,62 @@ impl DataAugmenterType for TrainingDataAugmenter {
         action: <Self::State as StateType>::Action,
         reward: f32,
         next_state: Self::State,
-    ) -> Vec<StateTransition> {
-        let mut state = state;
-        let mut action = action;
-        let mut next_state = next_state;
+    ) -> Vec<StateTransition<Self::State>> {
+        let mut transition = StateTransition::new(state, action, reward, next_state);
         let mut transitions = Vec::with_capacity(8);
 
-        transitions.push(StateTransition::new(
-            state.clone(),
-            action,
-            reward,
-            next_state.clone(),
-        ));
-        transitions.push(StateTransition::new(
-            state.mirrored_horizontally(),
-            action.mirrored_horizontally(),
-            reward,
-            next_state.mirrored_horizontally(),
-        ));
-
-        for _ in 0..3 {
-            state = state.rotated_cw();
-            action = action.rotated_cw();
-            next_state = next_state.rotated_cw();
-
-            transitions.push(StateTransition::new(
-                state.clone(),
-                action,
-                reward,
-                next_state.clone(),
-            ));
-            transitions.push(StateTransition::new(
-                state.mirrored_horizontally(),
-                action.mirrored_horizontally(),
-                reward,
-                next_state.mirrored_horizontally(),
-            ));
+        for rotation in 0..4 {
+            if rotation > 0 {
+                transition = transformed(&transition, rotated_cw, Direction::rotated_cw);
+            }
+            let mirrored = transformed(
+                &transition,
+                mirrored_horizontally,
+                Direction::mirrored_horizontally,
+            );
+            transitions.push(transition.clone());
+            transitions.push(mirrored);
         }
 
         transitions
     }
 }
 
-impl<R: GameRng> Board<R> {
-    fn mirrored_horizontally(&self) -> Self {
-        let mut tiles = [Tile::Empty; NUM_TILES];
-        let new_index = |row, column| row * NUM_ROWS + NUM_COLUMNS - 1 - column;
-
-        for row in 0..NUM_ROWS {
-            for column in 0..NUM_COLUMNS {
-                tiles[new_index(row, column)] = self
-                    .value_at(row, column)
-                    .map_or_else(|| Empty, |value| Value(value));
-            }
+// Moves the tiles of both boards, the action and the invalid actions the same way
+fn transformed(
+    transition: &StateTransition<Board<RealGameRng>>,
+    transform_cells: fn(u64) -> u64,
+    transform_direction: fn(&Direction) -> Direction,
+) -> StateTransition<Board<RealGameRng>> {
+    let mut invalid_actions_mask = transition.invalid_actions_mask & !0b1111;
+    for direction in Direction::ALL {
+        if transition.is_action_invalid(direction.index()) {
+            invalid_actions_mask |= 1 << transform_direction(&direction).index();
         }
-        Board::new_with_tiles_and_score(tiles, self.score, R::default())
     }
 
-    fn rotated_cw(&self) -> Self {
-        let mut tiles = [Tile::Empty; NUM_TILES];
-        let new_index = |row, column| NUM_COLUMNS * column + NUM_ROWS - row - 1;
-
-        for row in 0..NUM_ROWS {
-            for column in 0..NUM_COLUMNS {
-                tiles[new_index(row, column)] = self
-                    .value_at(row, column)
-                    .map_or_else(|| Empty, |value| Value(value));
-            }
-        }
-        Board::new_with_tiles_and_score(tiles, self.score, R::default())
+    StateTransition {
+        state: transform_cells(transition.state),
+        action: transform_direction(&Direction::ALL[transition.action as usize]).index() as u8,
+        reward: transition.reward,
+        next_state: transform_cells(transition.next_state),
+        invalid_actions_mask,
+        is_terminal: transition.is_terminal,
     }
 }
 
+// Cells are packed like BitBoard cells, 4 bits per tile and 16 bits per row
+fn mirrored_horizontally(cells: u64) -> u64 {
+    ((cells >> 12) & 0x000f_000f_000f_000f)
+        | ((cells >> 4) & 0x00f0_00f0_00f0_00f0)
+        | ((cells << 4) & 0x0f00_0f00_0f00_0f00)
+        | ((cells << 12) & 0xf000_f000_f000_f000)
+}
+
+fn rotated_cw(cells: u64) -> u64 {
+    mirrored_horizontally(transpose(cells))
+}
+
 impl Direction {
     fn mirrored_horizontally(&self) -> Self {
         match self {
@@ -113,7 +99,7 @@ impl Direction {
 mod tests {
     use super::*;
     use crate::game::board::Tile::{Empty, Value};
-    use crate::game::board::{Direction, Tile, NUM_TILES};
+    use crate::game::board::{Direction, NUM_TILES, Tile};
 
     #[test]
     fn board_mirroring() {
@@ -173,8 +159,8 @@ mod tests {
             let original_board = Board::new_with_tiles(original, RealGameRng::new());
             let mirrored_board = Board::new_with_tiles(mirrored, RealGameRng::new());
             assert_eq!(
-                original_board.mirrored_horizontally(),
-                mirrored_board,
+                mirrored_horizontally(original_board.pack()),
+                mirrored_board.pack(),
                 "Mismatch at index {}",
                 index
             );
@@ -239,14 +225,52 @@ mod tests {
             let original_board = Board::new_with_tiles(original, RealGameRng::new());
             let rotated_board = Board::new_with_tiles(rotated, RealGameRng::new());
             assert_eq!(
-                original_board.rotated_cw(),
-                rotated_board,
+                rotated_cw(original_board.pack()),
+                rotated_board.pack(),
                 "Mismatch at index {}",
                 index
             );
         }
     }
 
+    #[test]
+    fn invalid_actions_turn_with_the_board() {
+        // 2 4 8 16 in the first row, which can only move down
+        let mut tiles = [Empty; NUM_TILES];
+        tiles[..4].copy_from_slice(&[Value(2), Value(4), Value(8), Value(16)]);
+        let next_state = Board::new_with_tiles(tiles, RealGameRng::new_with_seed(0));
+        let mut tiles = [Empty; NUM_TILES];
+        tiles[..4].copy_from_slice(&[Value(2), Value(4), Value(8), Empty]);
+        tiles[7] = Value(16);
+        let state = Board::new_with_tiles(tiles, RealGameRng::new_with_seed(0));
+
+        let transitions =
+            TrainingDataAugmenter::default().augment(state, Direction::Up, 1.0, next_state);
+
+        assert_eq!(transitions.len(), 8);
+        let valid_actions = |transition: &StateTransition<Board<RealGameRng>>| {
+            Direction::ALL
+                .into_iter()
+                .filter(|direction| !transition.is_action_invalid(direction.index()))
+                .collect::<Vec<_>>()
+        };
+        // Original, mirrored, then rotated by 90 degrees and mirrored
+        assert_eq!(valid_actions(&transitions[0]), [Direction::Down]);
+        assert_eq!(valid_actions(&transitions[1]), [Direction::Down]);
+        assert_eq!(valid_actions(&transitions[2]), [Direction::Left]);
+        assert_eq!(valid_actions(&transitions[3]), [Direction::Right]);
+        assert_eq!(transitions[2].action, Direction::Right.index() as u8);
+        assert_eq!(
+            transitions[2].next_state,
+            rotated_cw(transitions[0].next_state)
+        );
+        assert!(
+            transitions
+                .iter()
+                .all(|transition| transition.reward == 1.0)
+        );
+    }
+
     #[test]
     fn direction_mirroring() {
         let original_directions = [
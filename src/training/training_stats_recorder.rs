@@ -1,27 +1,103 @@
-use crate::dqn::stats::StatsRecorderType;
+use crate::dqn::stats::{StatsRecorderType, TrainingStepStats};
 use crate::game::board::Board;
 use crate::game::game_rng::RealGameRng;
+use burn::serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "burn::serde")]
 pub(crate) struct TrainingStats {
     pub epochs: usize,
+    pub game_seed: u64,
     pub cumulated_epoch_rewards: f32,
     pub last_epoch_score: u32,
     pub last_epoch_length: u32,
     pub best_tile: u32,
     pub replay_buffer_size: usize,
     pub epsilon: f64,
+    // Sum of every reward term over the epoch, by name
+    #[serde(default)]
+    pub reward_terms: Vec<(String, f32)>,
+    // Training steps made during the epoch, the diagnostics below are their means and are
+    // missing for epochs without any
+    #[serde(default)]
+    pub training_steps: usize,
+    #[serde(default)]
+    pub mean_loss: Option<f32>,
+    #[serde(default)]
+    pub mean_q_value: Option<f32>,
+    #[serde(default)]
+    pub max_q_value: Option<f32>,
+    #[serde(default)]
+    pub mean_td_error: Option<f32>,
+    #[serde(default)]
+    pub gradient_norm: Option<f32>,
+    // Share of greedy actions whose best q-value belonged to an illegal action
+    #[serde(default)]
+    pub illegal_greedy_rate: Option<f32>,
+}
+
+impl Display for TrainingStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "epoch {}: seed {}, score {}, length {}, best tile {}, reward {:.3}, recorded moves {}, epsilon {:.5}",
+            self.epochs,
+            self.game_seed,
+            self.last_epoch_score,
+            self.last_epoch_length,
+            self.best_tile,
+            self.cumulated_epoch_rewards,
+            self.replay_buffer_size,
+            self.epsilon
+        )?;
+        for (name, value) in &self.reward_terms {
+            write!(f, ", {} {:.3}", name, value)?;
+        }
+        if let Some(mean_loss) = self.mean_loss {
+            write!(
+                f,
+                ", training steps {}, loss {:.5}, q {:.3} (max {:.3}), td error {:.4}, gradient norm {:.4}",
+                self.training_steps,
+                mean_loss,
+                self.mean_q_value.unwrap_or_default(),
+                self.max_q_value.unwrap_or_default(),
+                self.mean_td_error.unwrap_or_default(),
+                self.gradient_norm.unwrap_or_default(),
+            )?;
+        }
+        if let Some(illegal_greedy_rate) = self.illegal_greedy_rate {
+            write!(f, ", illegal greedy {:.1}%", illegal_greedy_rate * 100.0)?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Default)]
 pub(crate) struct TrainingStatsRecorder {
     epoch_number: usize,
+    game_seed: u64,
     reward_accumulator: f32,
     last_epoch_score: u32,
     last_epoch_length: u32,
     best_tile: u32,
     replay_buffer_size: usize,
     epsilon: f64,
+    reward_terms: Vec<(String, f32)>,
+    training_steps: usize,
+    loss_sum: f32,
+    q_value_sum: f32,
+    max_q_value: Option<f32>,
+    td_error_sum: f32,
+    gradient_norm_sum: f32,
+    greedy_actions: u32,
+    illegal_greedy_actions: u32,
+}
+
+impl TrainingStatsRecorder {
+    fn training_step_mean(&self, sum: f32) -> Option<f32> {
+        (self.training_steps > 0).then(|| sum / self.training_steps as f32)
+    }
 }
 
 impl StatsRecorderType for TrainingStatsRecorder {
@@ -34,12 +110,55 @@ impl StatsRecorderType for TrainingStatsRecorder {
         self.last_epoch_score = 0;
         self.best_tile = 0;
         self.epsilon = 0.0;
+        self.reward_terms.clear();
+        self.training_steps = 0;
+        self.loss_sum = 0.0;
+        self.q_value_sum = 0.0;
+        self.max_q_value = None;
+        self.td_error_sum = 0.0;
+        self.gradient_norm_sum = 0.0;
+        self.greedy_actions = 0;
+        self.illegal_greedy_actions = 0;
+    }
+
+    fn restore_epochs(&mut self, epochs: usize) {
+        self.epoch_number = epochs;
+    }
+
+    fn record_game_seed(&mut self, seed: u64) {
+        self.game_seed = seed;
     }
 
     fn record_reward(&mut self, reward: f32) {
         self.reward_accumulator += reward;
     }
 
+    fn record_reward_terms(&mut self, reward_terms: &[(&'static str, f32)]) {
+        for (name, value) in reward_terms {
+            match self.reward_terms.iter_mut().find(|(term, _)| term == name) {
+                Some((_, sum)) => *sum += value,
+                None => self.reward_terms.push((name.to_string(), *value)),
+            }
+        }
+    }
+
+    fn record_training_steps(&mut self, stats: &TrainingStepStats) {
+        self.training_steps += stats.num_steps;
+        self.loss_sum += stats.loss_sum;
+        self.q_value_sum += stats.q_value_sum;
+        self.max_q_value = Some(
+            self.max_q_value
+                .map_or(stats.max_q_value, |max| max.max(stats.max_q_value)),
+        );
+        self.td_error_sum += stats.td_error_sum;
+        self.gradient_norm_sum += stats.gradient_norm_sum;
+    }
+
+    fn record_greedy_actions(&mut self, num_actions: u32, num_illegal: u32) {
+        self.greedy_actions += num_actions;
+        self.illegal_greedy_actions += num_illegal;
+    }
+
     fn record_final_state(&mut self, state: &Self::State, epoch_length: u32) {
         self.last_epoch_score = state.score;
         self.last_epoch_length = epoch_length;
@@ -56,12 +175,56 @@ impl StatsRecorderType for TrainingStatsRecorder {
     fn stats(&self) -> Self::Stats {
         TrainingStats {
             epochs: self.epoch_number,
+            game_seed: self.game_seed,
             cumulated_epoch_rewards: self.reward_accumulator,
             last_epoch_score: self.last_epoch_score,
             last_epoch_length: self.last_epoch_length,
             best_tile: self.best_tile,
             replay_buffer_size: self.replay_buffer_size,
             epsilon: self.epsilon,
+            reward_terms: self.reward_terms.clone(),
+            training_steps: self.training_steps,
+            mean_loss: self.training_step_mean(self.loss_sum),
+            mean_q_value: self.training_step_mean(self.q_value_sum),
+            max_q_value: self.max_q_value,
+            mean_td_error: self.training_step_mean(self.td_error_sum),
+            gradient_norm: self.training_step_mean(self.gradient_norm_sum),
+            illegal_greedy_rate: (self.greedy_actions > 0)
+                .then(|| self.illegal_greedy_actions as f32 / self.greedy_actions as f32),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn training_steps_are_averaged_per_epoch() {
+        let mut recorder = TrainingStatsRecorder::default();
+        recorder.record_new_epoch();
+        let stats = recorder.stats();
+        assert_eq!(stats.training_steps, 0);
+        assert_eq!(stats.mean_loss, None);
+        assert_eq!(stats.illegal_greedy_rate, None);
+
+        recorder.record_training_steps(&TrainingStepStats {
+            num_steps: 2,
+            loss_sum: 4.0,
+            q_value_sum: 2.0,
+            max_q_value: 5.0,
+            td_error_sum: 1.0,
+            gradient_norm_sum: 0.5,
+        });
+        recorder.record_greedy_actions(4, 1);
+        let stats = recorder.stats();
+        assert_eq!(stats.training_steps, 2);
+        assert_eq!(stats.mean_loss, Some(2.0));
+        assert_eq!(stats.max_q_value, Some(5.0));
+        assert_eq!(stats.gradient_norm, Some(0.25));
+        assert_eq!(stats.illegal_greedy_rate, Some(0.25));
+
+        recorder.record_new_epoch();
+        assert_eq!(recorder.stats().mean_loss, None);
+    }
+}
@@ -1,28 +1,18 @@
-use crate::training::game_model::GameModelConfig;
-use crate::training::training_data_augmenter::TrainingDataAugmenter;
-use crate::training::training_stats_recorder::{TrainingStats, TrainingStatsRecorder};
-use crate::training::types::TrainingState::Training;
+use crate::training::episode_archive::EpisodeArchive;
+use crate::training::game_trainer::{GameTrainer, new_game_model, new_game_trainer};
+use crate::training::metrics_writer::MetricsWriter;
+use crate::training::model_storage;
+use crate::training::training_stats_recorder::TrainingStats;
 use crate::{
-    dqn::{
-        critic::CriticType,
-        model::Model,
-        trainer::{Hyperparameters, Trainer},
-    },
-    game::{board::Board, game_rng::RealGameRng},
+    dqn::hyperparameters::Hyperparameters,
     training::{
         game_model::GameModel,
-        training_critic::TrainingCritic,
-        types::{TrainingAction, TrainingMessage, TrainingState},
+        types::{TrainingAction, TrainingError, TrainingErrorKind, TrainingMessage, TrainingState},
     },
 };
-use burn::prelude::Device;
-use burn::record::{DefaultFileRecorder, FullPrecisionSettings, Recorder};
-use burn::{
-    Tensor,
-    module::Module,
-    prelude::{Backend, Float},
-    tensor::backend::AutodiffBackend,
-};
+use burn::tensor::backend::AutodiffBackend;
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
 use std::path::PathBuf;
 use std::sync::mpsc;
 use std::sync::mpsc::{Receiver, Sender, TryRecvError};
@@ -33,26 +23,25 @@ use std::time::Duration;
 pub(crate) struct TrainingThread<B: AutodiffBackend> {
     actions: Receiver<TrainingAction>,
     messages: Sender<TrainingMessage>,
-    trainer: Trainer<
-        B,
-        GameModel<B>,
-        Board<RealGameRng>,
-        TrainingCritic,
-        TrainingStatsRecorder,
-        TrainingDataAugmenter,
-    >,
+    trainer: GameTrainer<B>,
     training_state: TrainingState,
+    stats_history: Vec<TrainingStats>,
+    metrics_writer: Option<MetricsWriter>,
+    // Cleared on shutdown or once the UI drops its end of the actions channel
+    is_running: bool,
 }
 
 impl<B: AutodiffBackend> TrainingThread<B> {
-    pub(crate) fn spawn_thread() -> (
+    pub(crate) fn spawn_thread(
+        hyperparameters: Hyperparameters,
+    ) -> (
         Sender<TrainingAction>,
         Receiver<TrainingMessage>,
         JoinHandle<()>,
     ) {
         let (action_tx, action_rx) = mpsc::channel();
         let (message_tx, message_rx) = mpsc::channel();
-        let mut thread = TrainingThread::<B>::new(action_rx, message_tx);
+        let mut thread = TrainingThread::<B>::new(action_rx, message_tx, hyperparameters);
 
         let handle = thread::spawn(move || {
             thread.execute();
@@ -64,31 +53,29 @@ impl<B: AutodiffBackend> TrainingThread<B> {
     fn new(
         actions: Receiver<TrainingAction>,
         messages: Sender<TrainingMessage>,
+        hyperparameters: Hyperparameters,
     ) -> TrainingThread<B> {
-        let hyperparams = Hyperparameters::new();
-
         TrainingThread {
             actions,
             messages,
-            trainer: Trainer::new(
-                hyperparams,
-                TrainingCritic::new(),
-                TrainingDataAugmenter::default(),
-                Default::default(),
-            ),
+            trainer: new_game_trainer(hyperparameters, EpisodeArchive::default()),
             training_state: TrainingState::Idle,
+            stats_history: Vec::new(),
+            metrics_writer: None,
+            is_running: true,
         }
     }
 
     fn execute(&mut self) {
-        let mut model = GameModelConfig::new().init(&Default::default());
+        let mut model = new_game_model(self.trainer.config(), &Default::default());
 
         loop {
             model = self.handle_action(model);
+            if !self.is_running {
+                break;
+            }
             if self.training_state == TrainingState::Training {
-                let (updated_model, stats) = self.trainer.run_epoch(model);
-                model = updated_model;
-                self.report_progress(stats);
+                model = self.run_epoch(model);
             } else {
                 thread::sleep(Duration::from_millis(200));
             }
@@ -102,15 +89,11 @@ impl<B: AutodiffBackend> TrainingThread<B> {
                 match action {
                     TrainingAction::Pause => {
                         self.training_state = TrainingState::Idle;
-                        self.messages
-                            .send(TrainingMessage::StateChanged(TrainingState::Idle))
-                            .unwrap();
+                        self.send_message(TrainingMessage::StateChanged(TrainingState::Idle));
                     }
                     TrainingAction::Start => {
                         self.training_state = TrainingState::Training;
-                        self.messages
-                            .send(TrainingMessage::StateChanged(TrainingState::Training))
-                            .unwrap();
+                        self.send_message(TrainingMessage::StateChanged(TrainingState::Training));
                     }
                     TrainingAction::Save(file_path) => {
                         self.save_model(&model, file_path);
@@ -118,35 +101,251 @@ impl<B: AutodiffBackend> TrainingThread<B> {
                     TrainingAction::Load(file_path) => {
                         return self.load_model(model, file_path);
                     }
+                    TrainingAction::SaveCheckpoint(directory) => {
+                        self.save_checkpoint(&model, directory);
+                    }
+                    TrainingAction::LoadCheckpoint(directory) => {
+                        return self.load_checkpoint(model, directory);
+                    }
+                    TrainingAction::ExportMetrics(directory) => {
+                        self.export_metrics(directory);
+                    }
+                    TrainingAction::SetHyperparameters(hyperparameters) => {
+                        return self.set_hyperparameters(model, hyperparameters);
+                    }
+                    TrainingAction::ShareModel => {
+                        self.share_model(&model);
+                    }
+                    TrainingAction::Shutdown(autosave_directory) => {
+                        self.shutdown(&model, autosave_directory);
+                    }
                 }
             }
             Err(TryRecvError::Empty) => (),
-            Err(TryRecvError::Disconnected) => panic!("Training thread disconnected"),
+            Err(TryRecvError::Disconnected) => {
+                eprintln!("Training thread disconnected, stopping");
+                self.is_running = false;
+            }
         }
 
         model
     }
 
-    fn report_progress(&self, stats: TrainingStats) {
-        self.messages
-            .send(TrainingMessage::EpochFinished(stats))
-            .unwrap();
+    // A failed epoch pauses training. After a panic, training continues from the model as it was
+    // before the epoch.
+    fn run_epoch(&mut self, model: GameModel<B>) -> GameModel<B> {
+        let previous_model = model.clone();
+        match panic::catch_unwind(AssertUnwindSafe(|| self.trainer.run_epoch(model))) {
+            Ok((model, Ok(stats))) => {
+                self.report_progress(stats);
+                model
+            }
+            Ok((model, Err(error))) => {
+                self.stop_training(TrainingErrorKind::Actors, error.to_string());
+                model
+            }
+            Err(panic) => {
+                self.stop_training(TrainingErrorKind::Epoch, panic_message(panic));
+                previous_model
+            }
+        }
+    }
+
+    fn stop_training(&mut self, kind: TrainingErrorKind, message: String) {
+        self.report_error(kind, message);
+        self.training_state = TrainingState::Idle;
+        self.send_message(TrainingMessage::StateChanged(TrainingState::Idle));
+    }
+
+    fn report_progress(&mut self, stats: TrainingStats) {
+        self.write_metrics(&stats);
+        self.stats_history.push(stats.clone());
+        self.send_message(TrainingMessage::EpochFinished(stats));
+    }
+
+    // Messages are dropped once the UI is gone, the thread then stops at the next action check
+    fn send_message(&self, message: TrainingMessage) {
+        if self.messages.send(message).is_err() {
+            eprintln!("Training thread can't reach the UI anymore");
+        }
+    }
+
+    // Failed actions leave the thread running, with the state from before the action
+    fn report_error(&self, kind: TrainingErrorKind, message: String) {
+        let error = TrainingError::new(kind, message);
+        eprintln!("{}", error);
+        self.send_message(TrainingMessage::Error(error));
+    }
+
+    fn export_metrics(&mut self, directory: PathBuf) {
+        let metrics_writer = match MetricsWriter::replacing(directory.clone(), true) {
+            Ok(metrics_writer) => metrics_writer,
+            Err(error) => {
+                self.report_error(
+                    TrainingErrorKind::ExportMetrics,
+                    format!("{}: {}", directory.display(), error),
+                );
+                return;
+            }
+        };
+        self.metrics_writer = Some(metrics_writer);
+        for stats in self.stats_history.clone() {
+            self.write_metrics(&stats);
+        }
+    }
+
+    // Export stops at the first error instead of reporting it every epoch
+    fn write_metrics(&mut self, stats: &TrainingStats) {
+        let Some(metrics_writer) = &mut self.metrics_writer else {
+            return;
+        };
+        if let Err(error) = metrics_writer.write(stats) {
+            self.metrics_writer = None;
+            self.report_error(
+                TrainingErrorKind::ExportMetrics,
+                format!("{}, stopping the export", error),
+            );
+        }
     }
 
     fn save_model(&self, model: &GameModel<B>, file_path: PathBuf) {
-        let recorder = DefaultFileRecorder::<FullPrecisionSettings>::new();
+        if let Err(error) = model_storage::save_model(model, file_path.clone()) {
+            self.report_error(
+                TrainingErrorKind::SaveModel,
+                format!("{}: {}", file_path.display(), error),
+            );
+        }
+    }
+
+    // A model that can't be loaded leaves the current one in place. A loaded one brings its own
+    // architecture along.
+    fn load_model(&mut self, model: GameModel<B>, file_path: PathBuf) -> GameModel<B> {
+        match model_storage::load_model(file_path.clone(), &Default::default()) {
+            Ok(loaded_model) => {
+                self.trainer.replace_model(&loaded_model);
+                let architecture = loaded_model.config().architecture;
+                if architecture != self.trainer.config().model_architecture {
+                    self.trainer.set_model_architecture(architecture);
+                    let hyperparameters = self.trainer.config().clone();
+                    self.send_message(TrainingMessage::HyperparametersChanged(hyperparameters));
+                }
+                loaded_model
+            }
+            Err(error) => {
+                self.report_error(
+                    TrainingErrorKind::LoadModel,
+                    format!("{}: {}", file_path.display(), error),
+                );
+                model
+            }
+        }
+    }
+
+    fn share_model(&self, model: &GameModel<B>) {
+        match model_storage::share_model(model) {
+            Ok(shared_model) => self.send_message(TrainingMessage::ModelShared(shared_model)),
+            Err(error) => self.report_error(TrainingErrorKind::ShareModel, error.to_string()),
+        }
+    }
 
+    fn save_checkpoint(&self, model: &GameModel<B>, directory: PathBuf) {
+        if let Err(error) =
+            model_storage::save_checkpoint(&directory, model, &self.trainer, &self.stats_history)
+        {
+            self.report_error(
+                TrainingErrorKind::SaveCheckpoint,
+                format!("{}: {}", directory.display(), error),
+            );
+        }
+    }
+
+    // Errors are only printed, as the UI is closing
+    fn shutdown(&mut self, model: &GameModel<B>, autosave_directory: Option<PathBuf>) {
+        self.is_running = false;
+        if self.stats_history.is_empty() {
+            return;
+        }
+        let Some(directory) = autosave_directory else {
+            eprintln!(
+                "Quitting without saving the {} epochs trained, use --autosave DIR to keep them",
+                self.stats_history.len()
+            );
+            return;
+        };
+
+        println!("Saving a checkpoint to {}", directory.display());
+        if let Err(error) =
+            model_storage::save_checkpoint(&directory, model, &self.trainer, &self.stats_history)
+        {
+            eprintln!(
+                "Failed to save a checkpoint to {}: {}",
+                directory.display(),
+                error
+            );
+        }
+    }
+
+    // The model is recreated when its architecture changes
+    fn set_hyperparameters(
+        &mut self,
+        model: GameModel<B>,
+        hyperparameters: Hyperparameters,
+    ) -> GameModel<B> {
+        if !self.stats_history.is_empty() {
+            self.report_error(
+                TrainingErrorKind::Hyperparameters,
+                "Hyperparameters can't be changed after training has started".to_string(),
+            );
+            return model;
+        }
+        let model = if model.config().architecture == hyperparameters.model_architecture {
+            model
+        } else {
+            new_game_model(&hyperparameters, &Default::default())
+        };
+        self.trainer = new_game_trainer(hyperparameters, EpisodeArchive::default());
         model
-            .clone()
-            .save_file(file_path, &recorder)
-            .expect("Failed to save the model");
     }
 
-    fn load_model(&self, model: GameModel<B>, file_path: PathBuf) -> GameModel<B> {
-        let recorder = DefaultFileRecorder::<FullPrecisionSettings>::new();
+    // The current model and trainer are kept until the whole checkpoint has loaded
+    fn load_checkpoint(&mut self, model: GameModel<B>, directory: PathBuf) -> GameModel<B> {
+        let hyperparameters = match model_storage::load_checkpoint_hyperparameters(&directory) {
+            Ok(hyperparameters) => hyperparameters,
+            Err(error) => {
+                self.report_error(
+                    TrainingErrorKind::LoadCheckpoint,
+                    format!("{}: {}", directory.display(), error),
+                );
+                return model;
+            }
+        };
+        let mut trainer = new_game_trainer(hyperparameters.clone(), EpisodeArchive::default());
+        let (model, stats_history) =
+            match model_storage::load_checkpoint(&directory, &mut trainer, &Default::default()) {
+                Ok(checkpoint) => checkpoint,
+                Err(error) => {
+                    self.report_error(
+                        TrainingErrorKind::LoadCheckpoint,
+                        format!("{}: {}", directory.display(), error),
+                    );
+                    return model;
+                }
+            };
 
+        self.trainer = trainer;
+        self.send_message(TrainingMessage::HyperparametersChanged(hyperparameters));
+        self.stats_history = stats_history.clone();
+        self.send_message(TrainingMessage::HistoryRestored(stats_history));
         model
-            .load_file(file_path, &recorder, &Default::default())
-            .expect("Failed to load the model")
+    }
+}
+
+fn panic_message(panic: Box<dyn Any + Send>) -> String {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "The training thread panicked".to_string()
     }
 }
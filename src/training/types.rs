@@ -1,5 +1,8 @@
+use std::fmt::{Display, Formatter};
 use std::path::PathBuf;
 
+use crate::dqn::hyperparameters::Hyperparameters;
+use crate::training::model_storage::SharedModel;
 use crate::training::training_stats_recorder::TrainingStats;
 
 #[derive(Debug, PartialEq)]
@@ -14,10 +17,71 @@ pub(crate) enum TrainingAction {
     Pause,
     Save(PathBuf),
     Load(PathBuf),
+    SaveCheckpoint(PathBuf),
+    LoadCheckpoint(PathBuf),
+    // Writes the stats so far and those of every following epoch to the directory
+    ExportMetrics(PathBuf),
+    SetHyperparameters(Hyperparameters),
+    ShareModel,
+    // Stops the thread once the current epoch is over, after saving a checkpoint to the
+    // directory if one is given
+    Shutdown(Option<PathBuf>),
 }
 
 #[derive(Debug)]
 pub(crate) enum TrainingMessage {
     StateChanged(TrainingState),
     EpochFinished(TrainingStats),
+    HistoryRestored(Vec<TrainingStats>),
+    HyperparametersChanged(Hyperparameters),
+    ModelShared(SharedModel),
+    // An action that failed without stopping the thread, e.g. loading an incompatible model
+    Error(TrainingError),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum TrainingErrorKind {
+    SaveModel,
+    LoadModel,
+    SaveCheckpoint,
+    LoadCheckpoint,
+    ExportMetrics,
+    ShareModel,
+    Hyperparameters,
+    Actors,
+    Epoch,
+}
+
+impl TrainingErrorKind {
+    pub(crate) fn title(&self) -> &'static str {
+        match self {
+            TrainingErrorKind::SaveModel => "Failed to save the model",
+            TrainingErrorKind::LoadModel => "Failed to load the model",
+            TrainingErrorKind::SaveCheckpoint => "Failed to save the checkpoint",
+            TrainingErrorKind::LoadCheckpoint => "Failed to load the checkpoint",
+            TrainingErrorKind::ExportMetrics => "Failed to export the metrics",
+            TrainingErrorKind::ShareModel => "Failed to copy the model",
+            TrainingErrorKind::Hyperparameters => "Hyperparameters not applied",
+            TrainingErrorKind::Actors => "Self-play stopped",
+            TrainingErrorKind::Epoch => "Training epoch failed",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct TrainingError {
+    pub kind: TrainingErrorKind,
+    pub message: String,
+}
+
+impl TrainingError {
+    pub(crate) fn new(kind: TrainingErrorKind, message: String) -> TrainingError {
+        TrainingError { kind, message }
+    }
+}
+
+impl Display for TrainingError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.kind.title(), self.message)
+    }
 }
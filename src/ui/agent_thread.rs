@@ -0,0 +1,283 @@
+use std::path::PathBuf;
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender, channel};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use burn::{prelude::Backend, tensor::Device};
+use slint::{ComponentHandle, ModelRc, SharedString, Timer, VecModel, Weak};
+
+use crate::{
+    AgentPlayback, AppWindow, Game, UiQValue,
+    dqn::{
+        policy,
+        state::{ActionType, StateType},
+    },
+    game::{
+        board::{Board, Direction},
+        game_rng::RealGameRng,
+    },
+    training::{
+        game_model::GameModel,
+        model_storage::{self, SharedModel},
+    },
+    ui::{event_loop::update_ui, human_game::board_tiles},
+};
+
+const AUTOPLAY_MOVE_DELAY: Duration = Duration::from_millis(250);
+
+pub(crate) enum AgentAction {
+    SetModel(SharedModel),
+    LoadModel(PathBuf),
+    Play,
+    Pause,
+    Step,
+    NewGame,
+    SetSpeed(f32),
+    Hint {
+        board: Board<RealGameRng>,
+        autoplay: bool,
+    },
+    // Sent once the window has closed, the UI can't be updated anymore
+    Shutdown,
+}
+
+// Runs a copy of a model for the watch tab and for hints in the play tab, so that neither slows
+// down training
+pub(crate) struct AgentThread<B: Backend> {
+    ui_handle: Weak<AppWindow>,
+    device: Device<B>,
+    model: Option<GameModel<B>>,
+    board: Board<RealGameRng>,
+    is_playing: bool,
+    moves_per_second: f32,
+}
+
+impl<B: Backend> AgentThread<B> {
+    pub(crate) fn spawn_thread(
+        ui_handle: Weak<AppWindow>,
+    ) -> (Sender<AgentAction>, JoinHandle<()>) {
+        let (tx, rx) = channel();
+        let mut thread = Self::new(ui_handle);
+
+        let handle = thread::spawn(move || {
+            thread.execute(rx);
+        });
+
+        (tx, handle)
+    }
+
+    fn new(ui_handle: Weak<AppWindow>) -> Self {
+        Self {
+            ui_handle,
+            device: Default::default(),
+            model: None,
+            board: Board::new(),
+            is_playing: false,
+            moves_per_second: 4.0,
+        }
+    }
+
+    fn execute(&mut self, actions: Receiver<AgentAction>) {
+        self.update_board(true);
+
+        loop {
+            let action = if self.is_playing {
+                match actions.recv_timeout(self.move_interval()) {
+                    Ok(action) => Some(action),
+                    Err(RecvTimeoutError::Timeout) => None,
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            } else {
+                match actions.recv() {
+                    Ok(action) => Some(action),
+                    Err(_) => return,
+                }
+            };
+
+            match action {
+                Some(AgentAction::Shutdown) => return,
+                Some(action) => self.handle_action(action),
+                None => self.play_move(),
+            }
+        }
+    }
+
+    fn handle_action(&mut self, action: AgentAction) {
+        match action {
+            AgentAction::SetModel(shared_model) => {
+                match model_storage::model_from_shared(shared_model, &self.device) {
+                    Ok(model) => self.set_model(model, "current model".to_string()),
+                    Err(error) => self.show_error(format!("Failed to copy the model: {}", error)),
+                }
+            }
+            AgentAction::LoadModel(file_path) => {
+                match model_storage::load_model(file_path.clone(), &self.device) {
+                    Ok(model) => self.set_model(model, file_path.display().to_string()),
+                    Err(error) => self.show_error(format!(
+                        "Failed to load {}: {}",
+                        file_path.display(),
+                        error
+                    )),
+                }
+            }
+            AgentAction::Play => {
+                self.is_playing = self.model.is_some() && !self.board.is_over();
+                self.update_board(false);
+            }
+            AgentAction::Pause => {
+                self.is_playing = false;
+                self.update_board(false);
+            }
+            AgentAction::Step => {
+                self.play_move();
+            }
+            AgentAction::NewGame => {
+                self.board = Board::new();
+                self.update_board(true);
+            }
+            AgentAction::SetSpeed(moves_per_second) => {
+                self.moves_per_second = moves_per_second.max(0.1);
+            }
+            AgentAction::Hint { board, autoplay } => {
+                self.show_hint(board, autoplay);
+            }
+            // Handled by execute()
+            AgentAction::Shutdown => (),
+        }
+    }
+
+    fn set_model(&mut self, model: GameModel<B>, name: String) {
+        self.model = Some(model);
+        self.update_board(false);
+
+        let ui_handle = self.ui_handle.clone();
+        update_ui(move || {
+            let ui = ui_handle.unwrap();
+            let playback = ui.global::<AgentPlayback>();
+            playback.set_model_name(name.into());
+            playback.set_error("".into());
+        });
+    }
+
+    fn play_move(&mut self) {
+        let Some(model) = &self.model else {
+            self.is_playing = false;
+            return;
+        };
+        if self.board.is_over() {
+            self.is_playing = false;
+            self.update_board(false);
+            return;
+        }
+
+        let direction = policy::best_action(&self.board, model, &self.device);
+        self.board.move_in_direction(direction);
+        if self.board.is_over() {
+            self.is_playing = false;
+        }
+        self.update_board(false);
+    }
+
+    fn move_interval(&self) -> Duration {
+        Duration::from_secs_f32(1.0 / self.moves_per_second)
+    }
+
+    fn update_board(&self, all_tiles_appeared: bool) {
+        let tiles = board_tiles(&self.board, all_tiles_appeared);
+        let score = self.board.score as i32;
+        let is_over = self.board.is_over();
+        let (q_values, _) = self.q_values(&self.board);
+        let is_playing = self.is_playing;
+
+        let ui_handle = self.ui_handle.clone();
+        update_ui(move || {
+            let ui = ui_handle.unwrap();
+            let playback = ui.global::<AgentPlayback>();
+            playback.set_tiles(ModelRc::new(VecModel::from(tiles)));
+            playback.set_score(score);
+            playback.set_is_over(is_over);
+            playback.set_q_values(ModelRc::new(VecModel::from(q_values)));
+            playback.set_is_playing(is_playing);
+        });
+    }
+
+    // Q-values of the given board, along with the direction the model would pick
+    fn q_values(&self, board: &Board<RealGameRng>) -> (Vec<UiQValue>, Option<Direction>) {
+        let Some(model) = &self.model else {
+            return (Vec::new(), None);
+        };
+        if board.is_over() {
+            return (Vec::new(), None);
+        }
+
+        let q_values = policy::q_values(board, model, &self.device);
+        let possible_actions = board.possible_actions();
+        let best_direction = possible_actions
+            .iter()
+            .max_by(|lhs, rhs| q_values[lhs.index()].total_cmp(&q_values[rhs.index()]))
+            .copied();
+
+        let ui_q_values = Direction::ALL
+            .iter()
+            .map(|direction| UiQValue {
+                direction: direction_name(direction),
+                value: q_values[direction.index()],
+                is_legal: possible_actions.contains(direction),
+                is_best: Some(*direction) == best_direction,
+            })
+            .collect();
+
+        (ui_q_values, best_direction)
+    }
+
+    // The board of the play tab is owned by the UI thread, so autoplay moves are sent back
+    // through the Game global. They're delayed to let the tiles animate.
+    fn show_hint(&self, board: Board<RealGameRng>, autoplay: bool) {
+        let (q_values, best_direction) = self.q_values(&board);
+        let legal_q_values: Vec<UiQValue> = q_values
+            .into_iter()
+            .filter(|q_value| q_value.is_legal)
+            .collect();
+
+        let ui_handle = self.ui_handle.clone();
+        update_ui(move || {
+            let ui = ui_handle.unwrap();
+            let game = ui.global::<Game>();
+            game.set_hint(ModelRc::new(VecModel::from(legal_q_values)));
+            game.set_hint_direction(
+                best_direction
+                    .as_ref()
+                    .map(direction_name)
+                    .unwrap_or_default(),
+            );
+
+            if let Some(direction) = best_direction.filter(|_| autoplay) {
+                Timer::single_shot(AUTOPLAY_MOVE_DELAY, move || {
+                    let ui = ui_handle.unwrap();
+                    ui.global::<Game>()
+                        .invoke_autoplay_move(direction.as_ui_direction());
+                });
+            }
+        });
+    }
+
+    fn show_error(&self, error: String) {
+        eprintln!("{}", error);
+
+        let ui_handle = self.ui_handle.clone();
+        update_ui(move || {
+            let ui = ui_handle.unwrap();
+            ui.global::<AgentPlayback>().set_error(error.into());
+        });
+    }
+}
+
+fn direction_name(direction: &Direction) -> SharedString {
+    match direction {
+        Direction::Up => "up",
+        Direction::Down => "down",
+        Direction::Left => "left",
+        Direction::Right => "right",
+    }
+    .into()
+}
@@ -0,0 +1,5 @@
+// Runs an update of the window on the UI thread. Updates still queued while the window closes
+// arrive after the event loop ended and are dropped.
+pub(crate) fn update_ui(update: impl FnOnce() + Send + 'static) {
+    let _ = slint::invoke_from_event_loop(update);
+}
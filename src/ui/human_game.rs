@@ -0,0 +1,145 @@
+use std::sync::mpsc::Sender;
+
+use slint::{ModelRc, VecModel};
+
+use crate::{
+    Game, TileData, UiDirection,
+    game::{
+        board::{Board, Direction, NUM_COLUMNS, Tile},
+        game_rng::RealGameRng,
+    },
+    ui::agent_thread::AgentAction,
+};
+
+impl UiDirection {
+    pub(crate) fn as_direction(&self) -> Direction {
+        match self {
+            UiDirection::Up => Direction::Up,
+            UiDirection::Down => Direction::Down,
+            UiDirection::Left => Direction::Left,
+            UiDirection::Right => Direction::Right,
+        }
+    }
+}
+
+impl Direction {
+    pub(crate) fn as_ui_direction(&self) -> UiDirection {
+        match self {
+            Direction::Up => UiDirection::Up,
+            Direction::Down => UiDirection::Down,
+            Direction::Left => UiDirection::Left,
+            Direction::Right => UiDirection::Right,
+        }
+    }
+}
+
+// Board played by hand in the UI. Hints and autoplay moves come from the agent thread.
+pub(crate) struct HumanGame {
+    board: Board<RealGameRng>,
+    agent_tx: Sender<AgentAction>,
+    autoplay_moves_left: usize,
+}
+
+impl HumanGame {
+    pub(crate) fn new(agent_tx: Sender<AgentAction>) -> Self {
+        HumanGame {
+            board: Board::new(),
+            agent_tx,
+            autoplay_moves_left: 0,
+        }
+    }
+
+    pub(crate) fn new_game(&mut self, game: &Game) {
+        self.board = Board::new();
+        self.autoplay_moves_left = 0;
+        show_board(game, &self.board, true);
+        self.request_hint(game, false);
+    }
+
+    // Playing by hand stops autoplay
+    pub(crate) fn move_tiles(&mut self, game: &Game, direction: Direction) {
+        self.autoplay_moves_left = 0;
+        if self.play_move(game, direction) {
+            self.request_hint(game, false);
+        }
+    }
+
+    pub(crate) fn autoplay(&mut self, game: &Game, num_moves: usize) {
+        self.autoplay_moves_left = num_moves;
+        self.request_hint(game, num_moves > 0);
+    }
+
+    pub(crate) fn autoplay_move(&mut self, game: &Game, direction: Direction) {
+        if self.autoplay_moves_left == 0 {
+            return;
+        }
+
+        self.autoplay_moves_left -= 1;
+        if !self.play_move(game, direction) || self.board.is_over() {
+            self.autoplay_moves_left = 0;
+        }
+        self.request_hint(game, self.autoplay_moves_left > 0);
+    }
+
+    pub(crate) fn request_hint(&self, game: &Game, autoplay: bool) {
+        if !autoplay && !game.get_show_hint() {
+            return;
+        }
+
+        let hint = AgentAction::Hint {
+            board: self.board.clone(),
+            autoplay,
+        };
+        if self.agent_tx.send(hint).is_err() {
+            eprintln!("Couldn't reach the stopped agent thread, no hint requested");
+        }
+    }
+
+    pub(crate) fn show(&self, game: &Game) {
+        show_board(game, &self.board, true);
+    }
+
+    // Moves that don't change the board are ignored, so no tile is placed and nothing is redrawn
+    fn play_move(&mut self, game: &Game, direction: Direction) -> bool {
+        if self.board.is_over() {
+            return false;
+        }
+
+        let tiles = self.board.tiles();
+        self.board.move_in_direction(direction);
+        if self.board.tiles() == tiles {
+            return false;
+        }
+
+        show_board(game, &self.board, false);
+        true
+    }
+}
+
+fn show_board(game: &Game, board: &Board<RealGameRng>, all_tiles_appeared: bool) {
+    game.set_tiles(ModelRc::new(VecModel::from(board_tiles(
+        board,
+        all_tiles_appeared,
+    ))));
+    game.set_score(board.score as i32);
+    game.set_is_over(board.is_over());
+}
+
+// Either all tiles or only the last placed one are animated as new
+pub(crate) fn board_tiles(board: &Board<RealGameRng>, all_tiles_appeared: bool) -> Vec<TileData> {
+    let new_tile_position = board.last_new_tile().map(|new_tile| new_tile.position);
+    board
+        .tiles()
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, tile)| match tile {
+            Tile::Empty => None,
+            Tile::Value(value) => Some(TileData {
+                value: value as i32,
+                row: (index / NUM_COLUMNS) as i32,
+                column: (index % NUM_COLUMNS) as i32,
+                just_appeared: all_tiles_appeared || new_tile_position == Some(index),
+            }),
+        })
+        .collect()
+}
@@ -0,0 +1,34 @@
+use slint::{Model, ModelRc, VecModel};
+
+use crate::{
+    UiHyperparameter,
+    dqn::hyperparameters::{Hyperparameters, HyperparametersError},
+};
+
+pub(crate) fn hyperparameters_model(
+    hyperparameters: &Hyperparameters,
+) -> ModelRc<UiHyperparameter> {
+    let rows: Vec<UiHyperparameter> = hyperparameters
+        .values()
+        .into_iter()
+        .map(|(name, value)| UiHyperparameter {
+            name: name.into(),
+            value: value.into(),
+        })
+        .collect();
+
+    ModelRc::new(VecModel::from(rows))
+}
+
+pub(crate) fn hyperparameters_from_model(
+    model: &ModelRc<UiHyperparameter>,
+) -> Result<Hyperparameters, HyperparametersError> {
+    let values: Vec<(String, String)> = model
+        .iter()
+        .map(|row| (row.name.to_string(), row.value.to_string()))
+        .collect();
+    let hyperparameters = Hyperparameters::new().with_overrides(&values)?;
+    hyperparameters.validate()?;
+
+    Ok(hyperparameters)
+}
@@ -1,3 +1,7 @@
+pub(crate) mod agent_thread;
+pub(crate) mod event_loop;
+pub(crate) mod human_game;
+pub(crate) mod hyperparameters_settings;
 pub(crate) mod training_overview;
 pub(crate) mod training_state;
 pub(crate) mod training_update_adapter;
@@ -2,8 +2,13 @@ use plotters::prelude::*;
 use slint::{ComponentHandle, Image, Rgb8Pixel, SharedPixelBuffer, Weak};
 
 use crate::{
-    training::{training_stats_recorder::TrainingStats, types::TrainingState}, AppWindow, PlotSize, Plots,
-    UiTrainingStats,
+    dqn::hyperparameters::Hyperparameters,
+    training::{
+        training_stats_recorder::TrainingStats,
+        types::{TrainingError, TrainingState},
+    },
+    ui::{event_loop::update_ui, hyperparameters_settings::hyperparameters_model},
+    AppWindow, ErrorToast, HyperparametersSettings, PlotSize, Plots, UiTrainingStats,
 };
 
 use std::sync::{Arc, Mutex};
@@ -12,7 +17,7 @@ use std::{
     thread::{self, JoinHandle},
 };
 
-#[derive(Default)]
+#[derive(Default, PartialEq)]
 pub(crate) struct PlotsSizes {
     pub score_plot_size: PlotSize,
     pub epoch_legth_plot_size: PlotSize,
@@ -35,29 +40,19 @@ impl PlotsSizes {
         }
     }
 
-    pub(crate) fn from(area_size: PlotSize) -> PlotsSizes {
+    // The diagnostics plots add two more columns to the grid
+    pub(crate) fn from(area_size: PlotSize, show_diagnostics: bool) -> PlotsSizes {
         let spacing = 8;
-        let score = PlotSize {
-            width: (area_size.width - spacing) / 2,
-            height: (area_size.height - spacing) / 2,
-        };
-        let epoch_legth = PlotSize {
-            width: (area_size.width - spacing) / 2,
-            height: (area_size.height - spacing) / 2,
-        };
-        let reward = PlotSize {
-            width: (area_size.width - spacing) / 2,
-            height: (area_size.height - spacing) / 2,
-        };
-        let best_tile = PlotSize {
-            width: (area_size.width - spacing) / 2,
+        let num_columns = if show_diagnostics { 4 } else { 2 };
+        let plot_size = PlotSize {
+            width: (area_size.width - spacing * (num_columns - 1)) / num_columns,
             height: (area_size.height - spacing) / 2,
         };
         PlotsSizes {
-            score_plot_size: score,
-            epoch_legth_plot_size: epoch_legth,
-            reward_plot_size: reward,
-            best_tile_plot_size: best_tile,
+            score_plot_size: plot_size.clone(),
+            epoch_legth_plot_size: plot_size.clone(),
+            reward_plot_size: plot_size.clone(),
+            best_tile_plot_size: plot_size,
         }
     }
 }
@@ -77,6 +72,7 @@ pub(crate) enum PlotRangeType {
 pub(crate) struct PlotsSettings {
     pub is_log_scale_enabled: bool,
     pub range: PlotRangeType,
+    pub show_diagnostics: bool,
 }
 
 impl Default for PlotsSettings {
@@ -84,15 +80,21 @@ impl Default for PlotsSettings {
         Self {
             is_log_scale_enabled: false,
             range: PlotRangeType::All,
+            show_diagnostics: false,
         }
     }
 }
 
 pub(crate) enum TrainingOverviewUpdate {
     EpochFinished(TrainingStats),
+    HistoryRestored(Vec<TrainingStats>),
+    HyperparametersChanged(Hyperparameters),
     StateChanged(TrainingState),
-    PlotsSizesChanged(PlotsSizes),
+    PlotsAreaSizeChanged(PlotSize),
     PlotsSettingsChanged(PlotsSettings),
+    Error(TrainingError),
+    // Sent once the window has closed, the UI can't be updated anymore
+    Shutdown,
 }
 
 pub(crate) struct TrainingOverviewThread {
@@ -101,8 +103,15 @@ pub(crate) struct TrainingOverviewThread {
     epoch_length: Vec<u32>,
     rewards: Vec<f32>,
     best_tiles: Vec<u32>,
+    losses: Vec<Option<f32>>,
+    mean_q_values: Vec<Option<f32>>,
+    max_q_values: Vec<Option<f32>>,
+    td_errors: Vec<Option<f32>>,
+    gradient_norms: Vec<Option<f32>>,
     best_score: u32,
     best_tile: u32,
+    training_steps: usize,
+    plots_area_size: PlotSize,
     plots_sizes: PlotsSizes,
     plots_settings: PlotsSettings,
     epoch_per_second_counter: Arc<Mutex<u32>>,
@@ -134,8 +143,15 @@ impl TrainingOverviewThread {
             epoch_length: Vec::new(),
             rewards: Vec::new(),
             best_tiles: Vec::new(),
+            losses: Vec::new(),
+            mean_q_values: Vec::new(),
+            max_q_values: Vec::new(),
+            td_errors: Vec::new(),
+            gradient_norms: Vec::new(),
             best_score: 0,
             best_tile: 0,
+            training_steps: 0,
+            plots_area_size: Default::default(),
             plots_sizes: Default::default(),
             plots_settings: Default::default(),
             epoch_per_second_counter: Arc::new(Mutex::new(0)),
@@ -145,39 +161,88 @@ impl TrainingOverviewThread {
     fn execute(&mut self, updates: Receiver<TrainingOverviewUpdate>) {
         use TrainingOverviewUpdate::*;
 
-        loop {
-            for update in &updates {
-                match update {
-                    EpochFinished(stats) => {
-                        self.handle_new_epoch_stats(stats);
-                    }
-                    StateChanged(state) => {
-                        self.handle_new_state(state);
-                    }
-                    PlotsSizesChanged(sizes) => {
-                        self.handle_plot_size_change(sizes);
-                    }
-                    PlotsSettingsChanged(settings) => {
-                        self.handle_plots_settings_change(settings);
-                    }
+        for update in updates {
+            match update {
+                EpochFinished(stats) => {
+                    self.handle_new_epoch_stats(stats);
+                }
+                HistoryRestored(history) => {
+                    self.handle_restored_history(history);
+                }
+                HyperparametersChanged(hyperparameters) => {
+                    self.handle_new_hyperparameters(hyperparameters);
                 }
+                StateChanged(state) => {
+                    self.handle_new_state(state);
+                }
+                PlotsAreaSizeChanged(area_size) => {
+                    self.handle_plots_area_size_change(area_size);
+                }
+                PlotsSettingsChanged(settings) => {
+                    self.handle_plots_settings_change(settings);
+                }
+                Error(error) => {
+                    self.handle_error(error);
+                }
+                Shutdown => return,
             }
         }
     }
 
     fn handle_new_epoch_stats(&mut self, training_stats: TrainingStats) {
+        self.record_epoch_stats(&training_stats);
+        self.update_stats(training_stats);
+        self.update_plots();
+
+        let mut counter = self.epoch_per_second_counter.lock().unwrap();
+        *counter += 1;
+    }
+
+    fn handle_restored_history(&mut self, history: Vec<TrainingStats>) {
+        self.scores.clear();
+        self.epoch_length.clear();
+        self.rewards.clear();
+        self.best_tiles.clear();
+        self.losses.clear();
+        self.mean_q_values.clear();
+        self.max_q_values.clear();
+        self.td_errors.clear();
+        self.gradient_norms.clear();
+        self.best_score = 0;
+        self.best_tile = 0;
+        self.training_steps = 0;
+
+        for training_stats in &history {
+            self.record_epoch_stats(training_stats);
+        }
+        if let Some(last_stats) = history.into_iter().last() {
+            self.update_stats(last_stats);
+        }
+        self.update_plots();
+    }
+
+    fn record_epoch_stats(&mut self, training_stats: &TrainingStats) {
         self.scores.push(training_stats.last_epoch_score);
         self.epoch_length.push(training_stats.last_epoch_length);
         self.rewards.push(training_stats.cumulated_epoch_rewards);
         self.best_tiles.push(training_stats.best_tile);
         self.best_score = self.best_score.max(training_stats.last_epoch_score);
         self.best_tile = self.best_tile.max(training_stats.best_tile);
+        self.losses.push(training_stats.mean_loss);
+        self.mean_q_values.push(training_stats.mean_q_value);
+        self.max_q_values.push(training_stats.max_q_value);
+        self.td_errors.push(training_stats.mean_td_error);
+        self.gradient_norms.push(training_stats.gradient_norm);
+        self.training_steps += training_stats.training_steps;
+    }
 
+    fn update_stats(&self, training_stats: TrainingStats) {
         let best_score = self.best_score;
         let best_tile = self.best_tile;
+        let training_steps = self.training_steps;
         let ui_handle = self.ui_handle.clone();
 
-        slint::invoke_from_event_loop(move || {
+        update_ui(move || {
             let ui_handle = ui_handle.clone();
             let ui = ui_handle.unwrap();
             let stats = ui.global::<UiTrainingStats>();
@@ -187,104 +252,64 @@ impl TrainingOverviewThread {
             stats.set_best_tile(best_tile as i32);
             stats.set_recorded_states(training_stats.replay_buffer_size as i32);
             stats.set_epsilon(training_stats.epsilon as f32);
-        })
-        .unwrap();
-
-        self.update_plots();
-
-        let mut counter = self.epoch_per_second_counter.lock().unwrap();
-        *counter += 1;
+            stats.set_training_steps(training_steps as i32);
+            if let Some(illegal_greedy_rate) = training_stats.illegal_greedy_rate {
+                stats.set_illegal_greedy_rate(illegal_greedy_rate);
+            }
+        });
     }
 
     fn handle_new_state(&mut self, state: TrainingState) {
         let ui_handle = self.ui_handle.clone();
-        slint::invoke_from_event_loop(move || {
+        update_ui(move || {
             let ui_handle = ui_handle.clone();
             let ui = ui_handle.unwrap();
             let stats = ui.global::<UiTrainingStats>();
             stats.set_state(state.as_ui_training_state());
-        })
-        .unwrap();
+        });
     }
 
-    fn handle_plot_size_change(&mut self, plot_sizes: PlotsSizes) {
-        let mut score_plot = None;
-        let mut epoch_length_plot = None;
-        let mut reward_plot = None;
-        let mut best_tile_plot = None;
-
-        if self.plots_sizes.score_plot_size != plot_sizes.score_plot_size
-            && plot_sizes.score_plot_size.is_valid()
-        {
-            self.plots_sizes.score_plot_size = plot_sizes.score_plot_size;
-            score_plot = Some(render_score_plot(
-                "score per epoch",
-                &self.scores,
-                self.plots_sizes.score_plot_size.width as u32,
-                self.plots_sizes.score_plot_size.height as u32,
-                &self.plots_settings,
-            ));
-        }
-        if self.plots_sizes.epoch_legth_plot_size != plot_sizes.epoch_legth_plot_size
-            && plot_sizes.epoch_legth_plot_size.is_valid()
-        {
-            self.plots_sizes.epoch_legth_plot_size = plot_sizes.epoch_legth_plot_size;
-            epoch_length_plot = Some(render_score_plot(
-                "game length per epoch",
-                &self.epoch_length,
-                self.plots_sizes.epoch_legth_plot_size.width as u32,
-                self.plots_sizes.epoch_legth_plot_size.height as u32,
-                &self.plots_settings,
-            ));
-        }
-        if self.plots_sizes.reward_plot_size != plot_sizes.reward_plot_size
-            && plot_sizes.reward_plot_size.is_valid()
-        {
-            self.plots_sizes.reward_plot_size = plot_sizes.reward_plot_size;
-            reward_plot = Some(render_reward_plot(
-                "reward per epoch",
-                &self.rewards,
-                self.plots_sizes.reward_plot_size.width as u32,
-                self.plots_sizes.reward_plot_size.height as u32,
-                &self.plots_settings,
-            ));
-        }
-        if self.plots_sizes.best_tile_plot_size != plot_sizes.best_tile_plot_size
-            && plot_sizes.best_tile_plot_size.is_valid()
-        {
-            self.plots_sizes.best_tile_plot_size = plot_sizes.best_tile_plot_size;
-            best_tile_plot = Some(render_best_tile_plot(
-                "best tile per epoch",
-                &self.best_tiles,
-                self.plots_sizes.best_tile_plot_size.width as u32,
-                self.plots_sizes.best_tile_plot_size.height as u32,
-                &self.plots_settings,
-            ));
-        }
+    // A newer error replaces the one shown
+    fn handle_error(&mut self, error: TrainingError) {
+        let ui_handle = self.ui_handle.clone();
+        update_ui(move || {
+            let ui = ui_handle.unwrap();
+            let toast = ui.global::<ErrorToast>();
+            toast.set_title(error.kind.title().into());
+            toast.set_message(error.message.into());
+            toast.set_is_visible(true);
+        });
+    }
 
+    fn handle_new_hyperparameters(&mut self, hyperparameters: Hyperparameters) {
         let ui_handle = self.ui_handle.clone();
-        slint::invoke_from_event_loop(move || {
+        update_ui(move || {
             let ui = ui_handle.unwrap();
-            let plots = ui.global::<Plots>();
+            let settings = ui.global::<HyperparametersSettings>();
+            settings.set_values(hyperparameters_model(&hyperparameters));
+            settings.set_error("".into());
+        });
+    }
 
-            if let Some(score_plot) = score_plot {
-                plots.set_score_plot(Image::from_rgb8(score_plot));
-            }
-            if let Some(epoch_length_plot) = epoch_length_plot {
-                plots.set_epoch_length_plot(Image::from_rgb8(epoch_length_plot));
-            }
-            if let Some(reward_plot) = reward_plot {
-                plots.set_reward_plot(Image::from_rgb8(reward_plot));
-            }
-            if let Some(best_tile_plot) = best_tile_plot {
-                plots.set_best_tile_plot(Image::from_rgb8(best_tile_plot));
-            }
-        })
-        .unwrap();
+    // Plots are only rendered again when their size actually changed
+    fn handle_plots_area_size_change(&mut self, area_size: PlotSize) {
+        self.plots_area_size = area_size;
+        let plots_sizes = PlotsSizes::from(
+            self.plots_area_size.clone(),
+            self.plots_settings.show_diagnostics,
+        );
+        if plots_sizes != self.plots_sizes && plots_sizes.score_plot_size.is_valid() {
+            self.plots_sizes = plots_sizes;
+            self.update_plots();
+        }
     }
 
     fn handle_plots_settings_change(&mut self, settings: PlotsSettings) {
         self.plots_settings = settings;
+        self.plots_sizes = PlotsSizes::from(
+            self.plots_area_size.clone(),
+            self.plots_settings.show_diagnostics,
+        );
         self.update_plots();
     }
 
@@ -317,9 +342,18 @@ impl TrainingOverviewThread {
             self.plots_sizes.best_tile_plot_size.height as u32,
             &self.plots_settings,
         );
+        // Hidden plots are rendered once they're shown
+        let diagnostics_plots = self.plots_settings.show_diagnostics.then(|| {
+            [
+                self.render_loss_plot(),
+                self.render_q_value_plot(),
+                self.render_td_error_plot(),
+                self.render_gradient_norm_plot(),
+            ]
+        });
 
         let ui_handle = self.ui_handle.clone();
-        slint::invoke_from_event_loop(move || {
+        update_ui(move || {
             let ui = ui_handle.unwrap();
             let plots = ui.global::<Plots>();
 
@@ -327,8 +361,59 @@ impl TrainingOverviewThread {
             plots.set_epoch_length_plot(Image::from_rgb8(epoch_length_plot));
             plots.set_reward_plot(Image::from_rgb8(reward_plot));
             plots.set_best_tile_plot(Image::from_rgb8(best_tile_plot));
-        })
-        .unwrap();
+            if let Some([loss_plot, q_value_plot, td_error_plot, gradient_norm_plot]) =
+                diagnostics_plots
+            {
+                plots.set_loss_plot(Image::from_rgb8(loss_plot));
+                plots.set_q_value_plot(Image::from_rgb8(q_value_plot));
+                plots.set_td_error_plot(Image::from_rgb8(td_error_plot));
+                plots.set_gradient_norm_plot(Image::from_rgb8(gradient_norm_plot));
+            }
+        });
+    }
+
+    // The diagnostics plots are as big as the progress plots in the same row
+    fn render_loss_plot(&self) -> SharedPixelBuffer<Rgb8Pixel> {
+        render_diagnostics_plot(
+            "loss per epoch",
+            &[("loss", &self.losses[..], RED)],
+            self.plots_sizes.score_plot_size.width as u32,
+            self.plots_sizes.score_plot_size.height as u32,
+            &self.plots_settings,
+        )
+    }
+
+    fn render_q_value_plot(&self) -> SharedPixelBuffer<Rgb8Pixel> {
+        render_diagnostics_plot(
+            "predicted q-value per epoch",
+            &[
+                ("mean", &self.mean_q_values[..], RED),
+                ("max", &self.max_q_values[..], BLUE),
+            ],
+            self.plots_sizes.epoch_legth_plot_size.width as u32,
+            self.plots_sizes.epoch_legth_plot_size.height as u32,
+            &self.plots_settings,
+        )
+    }
+
+    fn render_td_error_plot(&self) -> SharedPixelBuffer<Rgb8Pixel> {
+        render_diagnostics_plot(
+            "td error per epoch",
+            &[("td error", &self.td_errors[..], RED)],
+            self.plots_sizes.reward_plot_size.width as u32,
+            self.plots_sizes.reward_plot_size.height as u32,
+            &self.plots_settings,
+        )
+    }
+
+    fn render_gradient_norm_plot(&self) -> SharedPixelBuffer<Rgb8Pixel> {
+        render_diagnostics_plot(
+            "gradient norm per epoch",
+            &[("gradient norm", &self.gradient_norms[..], RED)],
+            self.plots_sizes.best_tile_plot_size.width as u32,
+            self.plots_sizes.best_tile_plot_size.height as u32,
+            &self.plots_settings,
+        )
     }
 }
 
@@ -550,3 +635,91 @@ fn render_best_tile_plot(
 
     pixel_buffer
 }
+
+// Epochs without training steps have no values and are skipped
+fn render_diagnostics_plot(
+    caption: &str,
+    series: &[(&str, &[Option<f32>], RGBColor)],
+    width: u32,
+    height: u32,
+    settings: &PlotsSettings,
+) -> SharedPixelBuffer<Rgb8Pixel> {
+    let num_values = series
+        .iter()
+        .map(|(_, values, _)| values.len())
+        .max()
+        .unwrap_or(0);
+    let start_x: usize;
+    let end_x: usize;
+    let range = match settings.range {
+        PlotRangeType::All => {
+            start_x = 1;
+            end_x = num_values.max(width as usize);
+            0..num_values
+        }
+        PlotRangeType::LastEpochs(epochs) => {
+            start_x = num_values.saturating_sub(epochs) + 1;
+            end_x = start_x + epochs - 1;
+            num_values.saturating_sub(epochs)..num_values
+        }
+        PlotRangeType::Custom(start, end) => {
+            let start = start.min(num_values.saturating_sub(1));
+            let end = end.min(num_values.saturating_sub(1));
+            start_x = start;
+            end_x = end;
+            if num_values == 0 { 0..0 } else { start..end + 1 }
+        }
+    };
+
+    let visible_values: Vec<f32> = series
+        .iter()
+        .flat_map(|(_, values, _)| values[range.clone()].iter().flatten().copied())
+        .collect();
+    let min_y = visible_values.iter().copied().fold(0f32, f32::min);
+    let mut max_y = visible_values.iter().copied().fold(0f32, f32::max);
+    if max_y <= min_y {
+        max_y = min_y + 1.0;
+    }
+
+    let mut pixel_buffer = SharedPixelBuffer::new(width, height);
+    let backend = BitMapBackend::with_buffer(pixel_buffer.make_mut_bytes(), (width, height));
+
+    let root = backend.into_drawing_area();
+    root.fill(&WHITE).unwrap();
+
+    let mut chart = ChartBuilder::on(&root)
+        .caption(caption, ("sans-serif", 20))
+        .margin(8)
+        .x_label_area_size(20)
+        .y_label_area_size(40)
+        .build_cartesian_2d(start_x..end_x, min_y..max_y)
+        .expect("failed to build chart");
+
+    chart.configure_mesh().draw().unwrap();
+
+    for (name, values, color) in series {
+        let color = *color;
+        let points: Vec<(usize, f32)> = (start_x..=end_x)
+            .zip(&values[range.clone()])
+            .filter_map(|(x, y)| y.map(|y| (x, y)))
+            .collect();
+        chart
+            .draw_series(LineSeries::new(points, color))
+            .unwrap()
+            .label(*name)
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 16, y)], color));
+    }
+    if series.len() > 1 {
+        chart
+            .configure_series_labels()
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .draw()
+            .unwrap();
+    }
+
+    drop(chart);
+    drop(root);
+
+    pixel_buffer
+}
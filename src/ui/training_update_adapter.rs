@@ -3,7 +3,10 @@ use std::{
     thread::{self, JoinHandle},
 };
 
-use crate::{training::types::TrainingMessage, ui::training_overview::TrainingOverviewUpdate};
+use crate::{
+    training::types::TrainingMessage,
+    ui::{agent_thread::AgentAction, training_overview::TrainingOverviewUpdate},
+};
 
 pub(crate) struct TrainingUpdateAdapter {}
 
@@ -11,17 +14,32 @@ impl TrainingUpdateAdapter {
     pub(crate) fn spawn_thread(
         messages_rx: Receiver<TrainingMessage>,
         update_tx: Sender<TrainingOverviewUpdate>,
+        agent_tx: Sender<AgentAction>,
     ) -> JoinHandle<()> {
+        // Ends with the training thread. The receivers stop first when quitting, so messages sent
+        // while the training thread shuts down are dropped.
         thread::spawn(move || {
             for message in messages_rx {
                 match message {
                     TrainingMessage::StateChanged(state) => {
-                        update_tx
-                            .send(TrainingOverviewUpdate::StateChanged(state))
-                            .unwrap();
+                        let _ = update_tx.send(TrainingOverviewUpdate::StateChanged(state));
                     }
                     TrainingMessage::EpochFinished(stats) => {
-                        update_tx.send(TrainingOverviewUpdate::EpochFinished(stats));
+                        let _ = update_tx.send(TrainingOverviewUpdate::EpochFinished(stats));
+                    }
+                    TrainingMessage::HistoryRestored(history) => {
+                        let _ = update_tx.send(TrainingOverviewUpdate::HistoryRestored(history));
+                    }
+                    TrainingMessage::HyperparametersChanged(hyperparameters) => {
+                        let _ = update_tx.send(TrainingOverviewUpdate::HyperparametersChanged(
+                            hyperparameters,
+                        ));
+                    }
+                    TrainingMessage::ModelShared(shared_model) => {
+                        let _ = agent_tx.send(AgentAction::SetModel(shared_model));
+                    }
+                    TrainingMessage::Error(error) => {
+                        let _ = update_tx.send(TrainingOverviewUpdate::Error(error));
                     }
                 }
             }